@@ -1,14 +1,44 @@
-use anchor_lang::prelude::*;
+use anchor_lang::{prelude::*, solana_program::program::invoke, system_program};
 use anchor_spl::{
-    associated_token::AssociatedToken,
-    token_2022::{self, Token2022},
-    token_interface::{Mint, TokenAccount, transfer_checked, TransferChecked},
-};
-use spl_token_2022::{
-    extension::{ExtensionType, StateWithExtensions},
-    state::Mint as Token2022Mint,
+    associated_token::{self, get_associated_token_address_with_program_id, AssociatedToken},
+    token_2022::{
+        self,
+        spl_token_2022::{
+            extension::{
+                confidential_transfer, default_account_state::DefaultAccountState,
+                transfer_fee::TransferFeeConfig,
+                transfer_hook::TransferHook as TransferHookExtension,
+                BaseStateWithExtensions, ExtensionType, StateWithExtensions,
+            },
+            instruction::AuthorityType,
+            onchain,
+            state::{AccountState, Mint as Token2022Mint},
+        },
+        Token2022,
+    },
+    token_2022_extensions::{
+        default_account_state_initialize, group_member_pointer_initialize, group_pointer_initialize,
+        interest_bearing_mint_initialize, metadata_pointer_initialize,
+        mint_close_authority_initialize, non_transferable_mint_initialize,
+        permanent_delegate_initialize, spl_pod::optional_keys::OptionalNonZeroPubkey,
+        spl_token_metadata_interface::state::{Field, TokenMetadata},
+        token_group_initialize, token_member_initialize, token_metadata_initialize,
+        token_metadata_update_authority, token_metadata_update_field,
+        transfer_fee_initialize, transfer_hook_initialize, transfer_hook_update,
+        DefaultAccountStateInitialize, GroupMemberPointerInitialize, GroupPointerInitialize,
+        InterestBearingMintInitialize, TokenGroupInitialize, TokenMemberInitialize,
+        MetadataPointerInitialize, MintCloseAuthorityInitialize, NonTransferableMintInitialize,
+        PermanentDelegateInitialize, TokenMetadataInitialize, TokenMetadataUpdateAuthority,
+        TokenMetadataUpdateField, TransferFeeInitialize, TransferHookInitialize,
+        TransferHookUpdate,
+    },
+    token_interface::{
+        self, get_mint_extension_data, Mint, TokenAccount, TokenInterface, TransferChecked,
+    },
 };
 use num_integer::Roots;
+use spl_token_group_interface::state::TokenGroup;
+use transfer_hook::program::TransferHook as TransferHookProgram;
 
 declare_id!("BXkgQBaKiJS7AunZPYAHGqQ5qKz6gJ7vTjedNYM1FUkU");
 
@@ -16,16 +46,26 @@ declare_id!("BXkgQBaKiJS7AunZPYAHGqQ5qKz6gJ7vTjedNYM1FUkU");
 pub mod token2022_amm {
     use super::*;
 
-    /// Initialize a new AMM pool for Token-2022 assets
+    /// Initialize a new AMM pool for any pair of SPL Token and Token-2022
+    /// mints, each side using its mint's token program. Mints with extensions
+    /// the pool cannot support are rejected, and mints with high-risk
+    /// extensions are only accepted when `policy` allows them.
     pub fn initialize_pool(
         ctx: Context<InitializePool>,
         fee_rate: u64, // Fee rate in basis points (100 = 1%)
+        policy: ExtensionPolicy,
     ) -> Result<()> {
-        let pool = &mut ctx.accounts.pool;
-        
         // Validate fee rate
         require!(fee_rate <= 1000, AmmError::InvalidFeeRate); // Max 10%
         
+        // Validate mint extensions
+        let token_a_high_risk = check_mint_extensions(&ctx.accounts.token_a_mint, &policy)?;
+        let token_b_high_risk = check_mint_extensions(&ctx.accounts.token_b_mint, &policy)?;
+        let high_risk = token_a_high_risk || token_b_high_risk;
+        require!(!high_risk || policy.allow_high_risk, AmmError::HighRiskMintExtension);
+        
+        let pool = &mut ctx.accounts.pool;
+        
         // Initialize pool state
         pool.authority = ctx.accounts.authority.key();
         pool.token_a_mint = ctx.accounts.token_a_mint.key();
@@ -33,20 +73,154 @@ pub mod token2022_amm {
         pool.token_a_vault = ctx.accounts.token_a_vault.key();
         pool.token_b_vault = ctx.accounts.token_b_vault.key();
         pool.lp_token_mint = ctx.accounts.lp_token_mint.key();
+        pool.token_a_program = ctx.accounts.token_a_program.key();
+        pool.token_b_program = ctx.accounts.token_b_program.key();
+        pool.lp_token_program = ctx.accounts.lp_token_program.key();
         pool.fee_rate = fee_rate;
         pool.token_a_reserve = 0;
         pool.token_b_reserve = 0;
         pool.lp_token_supply = 0;
         pool.bump = ctx.bumps.pool;
+        pool.version = Pool::VERSION;
+        pool.high_risk = high_risk;
+        
+        // The vaults are owned by the pool PDA, which has to be exempt from the
+        // hook's destination whitelist before deposits and swaps can reach it
+        let hooked = [&ctx.accounts.token_a_mint, &ctx.accounts.token_b_mint]
+            .into_iter()
+            .any(|mint| transfer_hook_program_id(mint) == Some(transfer_hook::ID));
+        if hooked {
+            let transfer_hook_program = ctx
+                .accounts
+                .transfer_hook_program
+                .as_ref()
+                .ok_or(AmmError::MissingTransferHookAccounts)?;
+            let hook_trusted_program = ctx
+                .accounts
+                .hook_trusted_program
+                .as_ref()
+                .ok_or(AmmError::MissingTransferHookAccounts)?;
+            let pool_exemption = ctx
+                .accounts
+                .pool_exemption
+                .as_ref()
+                .ok_or(AmmError::MissingTransferHookAccounts)?;
+
+            let pool_seeds = &[
+                b"pool",
+                pool.token_a_mint.as_ref(),
+                pool.token_b_mint.as_ref(),
+                &[pool.bump],
+            ];
+
+            transfer_hook::cpi::register_exempt_authority(
+                CpiContext::new_with_signer(
+                    transfer_hook_program.to_account_info(),
+                    transfer_hook::cpi::accounts::RegisterExemptAuthority {
+                        payer: ctx.accounts.authority.to_account_info(),
+                        exempt_authority: pool.to_account_info(),
+                        trusted_program: hook_trusted_program.to_account_info(),
+                        exemption: pool_exemption.to_account_info(),
+                        system_program: ctx.accounts.system_program.to_account_info(),
+                    },
+                    &[pool_seeds],
+                ),
+                pool_seeds.iter().map(|seed| seed.to_vec()).collect(),
+            )?;
+        }
         
         msg!("Initialized AMM pool with fee rate: {} basis points", fee_rate);
+        if high_risk {
+            msg!("Pool flagged as high-risk because of its mints' extensions");
+        }
         
         Ok(())
     }
     
-    /// Add liquidity to the pool (first time or subsequent)
-    pub fn add_liquidity(
-        ctx: Context<AddLiquidity>,
+    /// Upgrade a pool created before version 1 by appending the token
+    /// programs and high-risk flag to its account. Such pools predate the
+    /// extension checks, so they are flagged as high-risk if a mint has a
+    /// high-risk or unsupported extension.
+    pub fn migrate_pool(ctx: Context<MigratePool>) -> Result<()> {
+        let pool_info = ctx.accounts.pool.to_account_info();
+        require!(
+            pool_info.data_len() == Pool::LEGACY_SPACE,
+            AmmError::PoolAlreadyMigrated
+        );
+        let legacy = {
+            let data = pool_info.try_borrow_data()?;
+            require!(
+                data[..8] == *Pool::DISCRIMINATOR,
+                ErrorCode::AccountDiscriminatorMismatch
+            );
+            LegacyPool::deserialize(&mut &data[8..])?
+        };
+        require_keys_eq!(
+            legacy.lp_token_mint,
+            ctx.accounts.lp_token_mint.key(),
+            ErrorCode::ConstraintAddress
+        );
+        
+        let policy = ExtensionPolicy {
+            allow_high_risk: true,
+            trusted_hook_programs: Vec::new(),
+        };
+        let high_risk = [&ctx.accounts.token_a_mint, &ctx.accounts.token_b_mint]
+            .into_iter()
+            .any(|mint| check_mint_extensions(mint, &policy).unwrap_or(true));
+        
+        let pool = Pool {
+            authority: legacy.authority,
+            token_a_mint: legacy.token_a_mint,
+            token_b_mint: legacy.token_b_mint,
+            token_a_vault: legacy.token_a_vault,
+            token_b_vault: legacy.token_b_vault,
+            lp_token_mint: legacy.lp_token_mint,
+            fee_rate: legacy.fee_rate,
+            token_a_reserve: legacy.token_a_reserve,
+            token_b_reserve: legacy.token_b_reserve,
+            lp_token_supply: legacy.lp_token_supply,
+            bump: legacy.bump,
+            version: Pool::VERSION,
+            high_risk,
+            token_a_program: *ctx.accounts.token_a_mint.to_account_info().owner,
+            token_b_program: *ctx.accounts.token_b_mint.to_account_info().owner,
+            lp_token_program: *ctx.accounts.lp_token_mint.to_account_info().owner,
+        };
+        
+        let rent_due = Rent::get()?
+            .minimum_balance(Pool::SPACE)
+            .saturating_sub(pool_info.lamports());
+        if rent_due > 0 {
+            system_program::transfer(
+                CpiContext::new(
+                    ctx.accounts.system_program.to_account_info(),
+                    system_program::Transfer {
+                        from: ctx.accounts.payer.to_account_info(),
+                        to: pool_info.clone(),
+                    },
+                ),
+                rent_due,
+            )?;
+        }
+        pool_info.resize(Pool::SPACE)?;
+        pool.try_serialize(&mut &mut pool_info.try_borrow_mut_data()?[..])?;
+        
+        msg!("Migrated pool {} to version {}", pool_info.key(), Pool::VERSION);
+        if high_risk {
+            msg!("Pool flagged as high-risk because of its mints' extensions");
+        }
+        
+        Ok(())
+    }
+    
+    /// Add liquidity to the pool (first time or subsequent). The remaining
+    /// accounts are one combined list of the extra accounts required by both
+    /// mints' transfer hooks; each transfer picks out its own by key.
+    /// Deposits of a mint with a sell tax are taxed like sells, so the user's
+    /// tax ledger is opened, at the user's expense, if it does not exist yet.
+    pub fn add_liquidity<'info>(
+        ctx: Context<'_, '_, 'info, 'info, AddLiquidity<'info>>,
         amount_a_desired: u64,
         amount_b_desired: u64,
         amount_a_min: u64,
@@ -55,14 +229,13 @@ pub mod token2022_amm {
         let pool = &mut ctx.accounts.pool;
         let clock = Clock::get()?;
         
-        let (amount_a, amount_b, lp_tokens_to_mint) = if pool.token_a_reserve == 0 && pool.token_b_reserve == 0 {
+        let first_deposit = pool.token_a_reserve == 0 && pool.token_b_reserve == 0;
+        
+        let (amount_a, amount_b) = if first_deposit {
             // First liquidity provision
             require!(amount_a_desired > 0 && amount_b_desired > 0, AmmError::InsufficientAmount);
             
-            let lp_tokens = (amount_a_desired as u128 * amount_b_desired as u128).sqrt() as u64;
-            require!(lp_tokens > 1000, AmmError::InsufficientLiquidity); // Minimum liquidity
-            
-            (amount_a_desired, amount_b_desired, lp_tokens - 1000) // Burn first 1000 tokens
+            (amount_a_desired, amount_b_desired)
         } else {
             // Subsequent liquidity provision
             let amount_b_optimal = amount_a_desired
@@ -74,13 +247,7 @@ pub mod token2022_amm {
             if amount_b_optimal <= amount_b_desired {
                 require!(amount_b_optimal >= amount_b_min, AmmError::InsufficientAmount);
                 
-                let lp_tokens = amount_a_desired
-                    .checked_mul(pool.lp_token_supply)
-                    .unwrap()
-                    .checked_div(pool.token_a_reserve)
-                    .unwrap();
-                    
-                (amount_a_desired, amount_b_optimal, lp_tokens)
+                (amount_a_desired, amount_b_optimal)
             } else {
                 let amount_a_optimal = amount_b_desired
                     .checked_mul(pool.token_a_reserve)
@@ -91,41 +258,71 @@ pub mod token2022_amm {
                 require!(amount_a_optimal <= amount_a_desired, AmmError::InsufficientAmount);
                 require!(amount_a_optimal >= amount_a_min, AmmError::InsufficientAmount);
                 
-                let lp_tokens = amount_b_desired
-                    .checked_mul(pool.lp_token_supply)
-                    .unwrap()
-                    .checked_div(pool.token_b_reserve)
-                    .unwrap();
-                    
-                (amount_a_optimal, amount_b_desired, lp_tokens)
+                (amount_a_optimal, amount_b_desired)
             }
         };
         
+        // The vaults only receive what is left after the mints' transfer fees,
+        // so LP tokens and reserves are based on the received amounts
+        let received_a = amount_a - transfer_fee(&ctx.accounts.token_a_mint, clock.epoch, amount_a)?;
+        let received_b = amount_b - transfer_fee(&ctx.accounts.token_b_mint, clock.epoch, amount_b)?;
+        
+        let lp_tokens_to_mint = if first_deposit {
+            let lp_tokens = (received_a as u128 * received_b as u128).sqrt() as u64;
+            require!(lp_tokens > 1000, AmmError::InsufficientLiquidity); // Minimum liquidity
+            
+            lp_tokens - 1000 // Burn first 1000 tokens
+        } else {
+            let lp_tokens_a = received_a
+                .checked_mul(pool.lp_token_supply)
+                .unwrap()
+                .checked_div(pool.token_a_reserve)
+                .unwrap();
+            let lp_tokens_b = received_b
+                .checked_mul(pool.lp_token_supply)
+                .unwrap()
+                .checked_div(pool.token_b_reserve)
+                .unwrap();
+                
+            lp_tokens_a.min(lp_tokens_b)
+        };
+        
+        for mint in [&ctx.accounts.token_a_mint, &ctx.accounts.token_b_mint] {
+            open_tax_ledger_if_missing(
+                mint,
+                &ctx.accounts.user,
+                &ctx.accounts.system_program,
+                ctx.remaining_accounts,
+            )?;
+        }
+        
         // Transfer tokens to pool vaults (with transfer hook support)
         transfer_checked_with_hook_support(
             CpiContext::new(
-                ctx.accounts.token_program.to_account_info(),
+                ctx.accounts.token_a_program.to_account_info(),
                 TransferChecked {
                     from: ctx.accounts.user_token_a.to_account_info(),
                     to: ctx.accounts.token_a_vault.to_account_info(),
                     authority: ctx.accounts.user.to_account_info(),
                     mint: ctx.accounts.token_a_mint.to_account_info(),
                 },
-            ),
+            )
+            .with_remaining_accounts(hook_accounts(&ctx.accounts.token_a_mint, ctx.remaining_accounts)?),
             amount_a,
             ctx.accounts.token_a_mint.decimals,
         )?;
         
         transfer_checked_with_hook_support(
             CpiContext::new(
-                ctx.accounts.token_program.to_account_info(),
+                ctx.accounts.token_b_program.to_account_info(),
                 TransferChecked {
                     from: ctx.accounts.user_token_b.to_account_info(),
                     to: ctx.accounts.token_b_vault.to_account_info(),
                     authority: ctx.accounts.user.to_account_info(),
                     mint: ctx.accounts.token_b_mint.to_account_info(),
                 },
-            ),
+            )
+            .with_remaining_accounts(hook_accounts(&ctx.accounts.token_b_mint, ctx.remaining_accounts)?),
             amount_b,
             ctx.accounts.token_b_mint.decimals,
         )?;
@@ -138,10 +335,10 @@ pub mod token2022_amm {
             &[pool.bump],
         ];
         
-        token_2022::mint_to(
+        token_interface::mint_to(
             CpiContext::new_with_signer(
-                ctx.accounts.token_program.to_account_info(),
-                token_2022::MintTo {
+                ctx.accounts.lp_token_program.to_account_info(),
+                token_interface::MintTo {
                     mint: ctx.accounts.lp_token_mint.to_account_info(),
                     to: ctx.accounts.user_lp_token.to_account_info(),
                     authority: pool.to_account_info(),
@@ -152,18 +349,19 @@ pub mod token2022_amm {
         )?;
         
         // Update pool state
-        pool.token_a_reserve += amount_a;
-        pool.token_b_reserve += amount_b;
+        pool.token_a_reserve += received_a;
+        pool.token_b_reserve += received_b;
         pool.lp_token_supply += lp_tokens_to_mint;
         
-        msg!("Added liquidity: {} token A, {} token B, minted {} LP tokens", amount_a, amount_b, lp_tokens_to_mint);
+        msg!("Added liquidity: {} token A, {} token B, minted {} LP tokens", received_a, received_b, lp_tokens_to_mint);
         
         Ok(())
     }
     
-    /// Remove liquidity from the pool
-    pub fn remove_liquidity(
-        ctx: Context<RemoveLiquidity>,
+    /// Remove liquidity from the pool. The remaining accounts are one combined
+    /// list of the extra accounts required by both mints' transfer hooks.
+    pub fn remove_liquidity<'info>(
+        ctx: Context<'_, '_, 'info, 'info, RemoveLiquidity<'info>>,
         lp_token_amount: u64,
         amount_a_min: u64,
         amount_b_min: u64,
@@ -183,14 +381,18 @@ pub mod token2022_amm {
             .checked_div(pool.lp_token_supply)
             .unwrap();
         
-        require!(amount_a >= amount_a_min, AmmError::InsufficientAmount);
-        require!(amount_b >= amount_b_min, AmmError::InsufficientAmount);
+        // Slippage limits apply to what the user receives after transfer fees
+        let epoch = Clock::get()?.epoch;
+        let received_a = amount_a - transfer_fee(&ctx.accounts.token_a_mint, epoch, amount_a)?;
+        let received_b = amount_b - transfer_fee(&ctx.accounts.token_b_mint, epoch, amount_b)?;
+        require!(received_a >= amount_a_min, AmmError::InsufficientAmount);
+        require!(received_b >= amount_b_min, AmmError::InsufficientAmount);
         
         // Burn LP tokens
-        token_2022::burn(
+        token_interface::burn(
             CpiContext::new(
-                ctx.accounts.token_program.to_account_info(),
-                token_2022::Burn {
+                ctx.accounts.lp_token_program.to_account_info(),
+                token_interface::Burn {
                     mint: ctx.accounts.lp_token_mint.to_account_info(),
                     from: ctx.accounts.user_lp_token.to_account_info(),
                     authority: ctx.accounts.user.to_account_info(),
@@ -209,7 +411,7 @@ pub mod token2022_amm {
         
         transfer_checked_with_hook_support(
             CpiContext::new_with_signer(
-                ctx.accounts.token_program.to_account_info(),
+                ctx.accounts.token_a_program.to_account_info(),
                 TransferChecked {
                     from: ctx.accounts.token_a_vault.to_account_info(),
                     to: ctx.accounts.user_token_a.to_account_info(),
@@ -217,14 +419,15 @@ pub mod token2022_amm {
                     mint: ctx.accounts.token_a_mint.to_account_info(),
                 },
                 &[pool_seeds],
-            ),
+            )
+            .with_remaining_accounts(hook_accounts(&ctx.accounts.token_a_mint, ctx.remaining_accounts)?),
             amount_a,
             ctx.accounts.token_a_mint.decimals,
         )?;
         
         transfer_checked_with_hook_support(
             CpiContext::new_with_signer(
-                ctx.accounts.token_program.to_account_info(),
+                ctx.accounts.token_b_program.to_account_info(),
                 TransferChecked {
                     from: ctx.accounts.token_b_vault.to_account_info(),
                     to: ctx.accounts.user_token_b.to_account_info(),
@@ -232,7 +435,8 @@ pub mod token2022_amm {
                     mint: ctx.accounts.token_b_mint.to_account_info(),
                 },
                 &[pool_seeds],
-            ),
+            )
+            .with_remaining_accounts(hook_accounts(&ctx.accounts.token_b_mint, ctx.remaining_accounts)?),
             amount_b,
             ctx.accounts.token_b_mint.decimals,
         )?;
@@ -242,33 +446,716 @@ pub mod token2022_amm {
         pool.token_b_reserve -= amount_b;
         pool.lp_token_supply -= lp_token_amount;
         
-        msg!("Removed liquidity: {} LP tokens, returned {} token A, {} token B", lp_token_amount, amount_a, amount_b);
+        msg!("Removed liquidity: {} LP tokens, returned {} token A, {} token B", lp_token_amount, received_a, received_b);
         
         Ok(())
     }
     
-    /// Swap tokens in the pool
     /// Create a new Token-2022 mint with transfer hook
     pub fn create_token_with_hook(
         ctx: Context<CreateTokenWithHook>,
-        name: String,
-        symbol: String,
+        metadata: TokenMetadataArgs,
         decimals: u8,
         total_supply: u64,
+        extensions: ExtensionConfig,
+        max_supply: Option<u64>,
     ) -> Result<()> {
-        let mint = &mut ctx.accounts.mint;
+        let mint = &ctx.accounts.mint;
         let transfer_hook_program = &ctx.accounts.transfer_hook_program;
+        let token_program = ctx.accounts.token_program.to_account_info();
         
-        // Initialize the mint with transfer hook extension
-        // This will be handled by the Token-2022 program
         msg!("Creating Token-2022 mint with transfer hook");
         msg!("Mint: {}", mint.key());
         msg!("Transfer Hook Program: {}", transfer_hook_program.key());
         
+        require!(
+            !(extensions.recovery && extensions.permanent_delegate.is_some()),
+            AmmError::InvalidExtensionConfig
+        );
+        // Accounts start frozen, so the initial supply could not be minted
+        require!(
+            !(extensions.default_account_frozen && total_supply > 0),
+            AmmError::InvalidExtensionConfig
+        );
+        if let Some(max_supply) = max_supply {
+            require!(total_supply <= max_supply, AmmError::SupplyCapExceeded);
+        }
+        require!(
+            max_supply.is_some() == ctx.accounts.supply_config.is_some(),
+            AmmError::InvalidSupplyConfig
+        );
+        
+        // Mint extensions have to be initialized before the mint itself
+        let mut extension_types = vec![ExtensionType::TransferHook, ExtensionType::MetadataPointer];
+        extension_types.extend(extensions.extension_types());
+        let group_mint = ctx.accounts.group_mint.as_ref();
+        if let Some(group_mint) = group_mint {
+            let group = get_mint_extension_data::<TokenGroup>(&group_mint.to_account_info())
+                .map_err(|_| AmmError::InvalidTokenGroup)?;
+            require!(
+                u64::from(group.size) < u64::from(group.max_size),
+                AmmError::TokenGroupFull
+            );
+            extension_types.push(ExtensionType::GroupMemberPointer);
+        }
+        let space = ExtensionType::try_calculate_account_len::<Token2022Mint>(&extension_types)?;
+        
+        // Token-2022 grows the mint to fit the metadata and group membership
+        // itself, but the rent for them has to be there up front
+        let mut allocated_types = extension_types.clone();
+        if group_mint.is_some() {
+            allocated_types.push(ExtensionType::TokenGroupMember);
+        }
+        let allocated_space =
+            ExtensionType::try_calculate_account_len::<Token2022Mint>(&allocated_types)?;
+        let token_metadata = TokenMetadata {
+            update_authority: OptionalNonZeroPubkey::try_from(Some(ctx.accounts.payer.key()))?,
+            mint: mint.key(),
+            name: metadata.name.clone(),
+            symbol: metadata.symbol.clone(),
+            uri: metadata.uri.clone(),
+            additional_metadata: metadata
+                .additional_metadata
+                .iter()
+                .map(|field| (field.key.clone(), field.value.clone()))
+                .collect(),
+        };
+        let lamports = Rent::get()?.minimum_balance(allocated_space + token_metadata.tlv_size_of()?);
+        system_program::create_account(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                system_program::CreateAccount {
+                    from: ctx.accounts.payer.to_account_info(),
+                    to: mint.to_account_info(),
+                },
+            ),
+            lamports,
+            space as u64,
+            &token_2022::ID,
+        )?;
+
+        transfer_hook_initialize(
+            CpiContext::new(
+                token_program.clone(),
+                TransferHookInitialize {
+                    token_program_id: token_program.clone(),
+                    mint: mint.to_account_info(),
+                },
+            ),
+            Some(ctx.accounts.payer.key()),
+            Some(transfer_hook_program.key()),
+        )?;
+        
+        // The metadata lives in the mint account itself
+        metadata_pointer_initialize(
+            CpiContext::new(
+                token_program.clone(),
+                MetadataPointerInitialize {
+                    token_program_id: token_program.clone(),
+                    mint: mint.to_account_info(),
+                },
+            ),
+            Some(ctx.accounts.payer.key()),
+            Some(mint.key()),
+        )?;
+        
+        // Membership is also stored in the mint account itself
+        if group_mint.is_some() {
+            group_member_pointer_initialize(
+                CpiContext::new(
+                    token_program.clone(),
+                    GroupMemberPointerInitialize {
+                        token_program_id: token_program.clone(),
+                        mint: mint.to_account_info(),
+                    },
+                ),
+                Some(ctx.accounts.payer.key()),
+                Some(mint.key()),
+            )?;
+        }
+
+        initialize_selected_extensions(
+            &extensions,
+            &mint.to_account_info(),
+            &ctx.accounts.payer.key(),
+            &token_program,
+            ctx.program_id,
+        )?;
+        
+        // Hand freeze authority to the hook's PDA so it can keep freeze state
+        // consistent with whitelist membership
+        let (freeze_authority, _) = Pubkey::find_program_address(
+            &[b"freeze-authority", mint.key().as_ref()],
+            &transfer_hook_program.key(),
+        );
+        token_2022::initialize_mint2(
+            CpiContext::new(
+                token_program.clone(),
+                token_2022::InitializeMint2 {
+                    mint: mint.to_account_info(),
+                },
+            ),
+            decimals,
+            &ctx.accounts.payer.key(),
+            Some(&freeze_authority),
+        )?;
+        
+        token_metadata_initialize(
+            CpiContext::new(
+                token_program.clone(),
+                TokenMetadataInitialize {
+                    program_id: token_program.clone(),
+                    metadata: mint.to_account_info(),
+                    update_authority: ctx.accounts.payer.to_account_info(),
+                    mint_authority: ctx.accounts.payer.to_account_info(),
+                    mint: mint.to_account_info(),
+                },
+            ),
+            metadata.name,
+            metadata.symbol,
+            metadata.uri,
+        )?;
+        for field in metadata.additional_metadata {
+            token_metadata_update_field(
+                CpiContext::new(
+                    token_program.clone(),
+                    TokenMetadataUpdateField {
+                        program_id: token_program.clone(),
+                        metadata: mint.to_account_info(),
+                        update_authority: ctx.accounts.payer.to_account_info(),
+                    },
+                ),
+                Field::Key(field.key),
+                field.value,
+            )?;
+        }
+        
+        // The payer has to be the group's update authority
+        if let Some(group_mint) = group_mint {
+            token_member_initialize(CpiContext::new(
+                token_program.clone(),
+                TokenMemberInitialize {
+                    program_id: token_program.clone(),
+                    member: mint.to_account_info(),
+                    member_mint: mint.to_account_info(),
+                    member_mint_authority: ctx.accounts.payer.to_account_info(),
+                    group: group_mint.to_account_info(),
+                    group_update_authority: ctx.accounts.payer.to_account_info(),
+                },
+            ))?;
+            msg!("Added mint {} to group {}", mint.key(), group_mint.key());
+        }
+        
+        // Confidential transfers hide amounts from the hook. Record them as
+        // permitted in the hook's policy, which refuses while amount-based
+        // rules exist and keeps such rules from being added afterwards.
+        if extensions.confidential_transfers && transfer_hook_program.key() == transfer_hook::ID {
+            let hook_transfer_limits = ctx
+                .accounts
+                .hook_transfer_limits
+                .as_ref()
+                .ok_or(AmmError::MissingTransferHookAccounts)?;
+            let hook_sell_tax_config = ctx
+                .accounts
+                .hook_sell_tax_config
+                .as_ref()
+                .ok_or(AmmError::MissingTransferHookAccounts)?;
+            let confidential_policy = ctx
+                .accounts
+                .confidential_policy
+                .as_ref()
+                .ok_or(AmmError::MissingTransferHookAccounts)?;
+
+            let hook_rule_authority = ctx
+                .accounts
+                .hook_rule_authority
+                .as_ref()
+                .ok_or(AmmError::MissingTransferHookAccounts)?;
+
+            transfer_hook::cpi::initialize_confidential_transfer_policy(
+                CpiContext::new(
+                    transfer_hook_program.to_account_info(),
+                    transfer_hook::cpi::accounts::InitializeConfidentialTransferPolicy {
+                        authority: ctx.accounts.payer.to_account_info(),
+                        mint: mint.to_account_info(),
+                        rule_authority: hook_rule_authority.to_account_info(),
+                        transfer_limits: hook_transfer_limits.to_account_info(),
+                        sell_tax_config: hook_sell_tax_config.to_account_info(),
+                        confidential_policy: confidential_policy.to_account_info(),
+                        system_program: ctx.accounts.system_program.to_account_info(),
+                    },
+                ),
+                true,
+            )?;
+        }
+        
+        // Other hook programs have to initialize their own meta list
+        if transfer_hook_program.key() == transfer_hook::ID {
+            let extra_account_meta_list = ctx
+                .accounts
+                .extra_account_meta_list
+                .as_ref()
+                .ok_or(AmmError::MissingTransferHookAccounts)?;
+            let hook_sanctions_config = ctx
+                .accounts
+                .hook_sanctions_config
+                .as_ref()
+                .ok_or(AmmError::MissingTransferHookAccounts)?;
+            let hook_recovery_config = ctx
+                .accounts
+                .hook_recovery_config
+                .as_ref()
+                .ok_or(AmmError::MissingTransferHookAccounts)?;
+
+            transfer_hook::cpi::initialize_extra_account_meta_list(CpiContext::new(
+                transfer_hook_program.to_account_info(),
+                transfer_hook::cpi::accounts::InitializeExtraAccountMetaList {
+                    payer: ctx.accounts.payer.to_account_info(),
+                    extra_account_meta_list: extra_account_meta_list.to_account_info(),
+                    mint: mint.to_account_info(),
+                    system_program: ctx.accounts.system_program.to_account_info(),
+                    sanctions_config: hook_sanctions_config.to_account_info(),
+                    recovery_config: hook_recovery_config.to_account_info(),
+                },
+            ))?;
+        }
+        
+        // Mint the initial supply to the payer
+        associated_token::create(CpiContext::new(
+            ctx.accounts.associated_token_program.to_account_info(),
+            associated_token::Create {
+                payer: ctx.accounts.payer.to_account_info(),
+                associated_token: ctx.accounts.payer_token_account.to_account_info(),
+                authority: ctx.accounts.payer.to_account_info(),
+                mint: mint.to_account_info(),
+                system_program: ctx.accounts.system_program.to_account_info(),
+                token_program: token_program.clone(),
+            },
+        ))?;
+        if total_supply > 0 {
+            token_2022::mint_to(
+                CpiContext::new(
+                    token_program,
+                    token_2022::MintTo {
+                        mint: mint.to_account_info(),
+                        to: ctx.accounts.payer_token_account.to_account_info(),
+                        authority: ctx.accounts.payer.to_account_info(),
+                    },
+                ),
+                total_supply,
+            )?;
+        }
+        
+        msg!("Minted {} tokens to {}", total_supply, ctx.accounts.payer_token_account.key());
+        
+        // With a supply cap, mint authority moves to a PDA of this program so
+        // further supply can only be minted through `mint_more`
+        if let (Some(max_supply), Some(supply_config)) =
+            (max_supply, ctx.accounts.supply_config.as_mut())
+        {
+            let mint_key = mint.key();
+            supply_config.authority = ctx.accounts.payer.key();
+            supply_config.mint = mint_key;
+            supply_config.max_supply = max_supply;
+            supply_config.bump = ctx.bumps.supply_config.ok_or(AmmError::InvalidSupplyConfig)?;
+            
+            // The hook gates its rules on the mint authority, so rule
+            // configuration stays with the payer
+            if transfer_hook_program.key() == transfer_hook::ID {
+                let hook_rule_authority = ctx
+                    .accounts
+                    .hook_rule_authority
+                    .as_ref()
+                    .ok_or(AmmError::MissingTransferHookAccounts)?;
+                
+                transfer_hook::cpi::initialize_rule_authority(
+                    CpiContext::new(
+                        transfer_hook_program.to_account_info(),
+                        transfer_hook::cpi::accounts::InitializeRuleAuthority {
+                            payer: ctx.accounts.payer.to_account_info(),
+                            mint_authority: ctx.accounts.payer.to_account_info(),
+                            mint: mint.to_account_info(),
+                            rule_authority: hook_rule_authority.to_account_info(),
+                            system_program: ctx.accounts.system_program.to_account_info(),
+                        },
+                    ),
+                    ctx.accounts.payer.key(),
+                )?;
+            }
+            
+            let (mint_authority, _) =
+                Pubkey::find_program_address(&[b"mint-authority", mint_key.as_ref()], ctx.program_id);
+            token_2022::set_authority(
+                CpiContext::new(
+                    ctx.accounts.token_program.to_account_info(),
+                    token_2022::SetAuthority {
+                        current_authority: ctx.accounts.payer.to_account_info(),
+                        account_or_mint: mint.to_account_info(),
+                    },
+                ),
+                AuthorityType::MintTokens,
+                Some(mint_authority),
+            )?;
+            
+            msg!("Supply capped at {}", max_supply);
+        }
+        
+        Ok(())
+    }
+
+    /// Mint more of a capped token, up to its supply cap
+    pub fn mint_more(ctx: Context<MintMore>, amount: u64) -> Result<()> {
+        let mint = &ctx.accounts.mint;
+        let supply_config = &ctx.accounts.supply_config;
+        
+        let new_supply = mint
+            .supply
+            .checked_add(amount)
+            .ok_or(AmmError::SupplyCapExceeded)?;
+        require!(new_supply <= supply_config.max_supply, AmmError::SupplyCapExceeded);
+        
+        let mint_key = mint.key();
+        let mint_authority_seeds = &[
+            b"mint-authority",
+            mint_key.as_ref(),
+            &[ctx.bumps.mint_authority],
+        ];
+        token_2022::mint_to(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                token_2022::MintTo {
+                    mint: mint.to_account_info(),
+                    to: ctx.accounts.destination.to_account_info(),
+                    authority: ctx.accounts.mint_authority.to_account_info(),
+                },
+                &[mint_authority_seeds],
+            ),
+            amount,
+        )?;
+        
+        msg!("Minted {} tokens, supply now {}/{}", amount, new_supply, supply_config.max_supply);
+        
         Ok(())
     }
 
-    /// Initialize transfer hook for an existing token
+    /// Irreversibly revoke the mint and/or freeze authority of a capped token.
+    /// Revoking the mint authority fixes the supply at its current value.
+    pub fn revoke_authorities(
+        ctx: Context<RevokeAuthorities>,
+        revoke_mint_authority: bool,
+        revoke_freeze_authority: bool,
+    ) -> Result<()> {
+        let mint = &ctx.accounts.mint;
+        let mint_key = mint.key();
+        let mint_authority_seeds = &[
+            b"mint-authority",
+            mint_key.as_ref(),
+            &[ctx.bumps.mint_authority],
+        ];
+        
+        // The hook checks that the signer is the mint's rule authority
+        if revoke_freeze_authority {
+            let transfer_hook_program = ctx
+                .accounts
+                .transfer_hook_program
+                .as_ref()
+                .ok_or(AmmError::MissingTransferHookAccounts)?;
+            let hook_freeze_authority = ctx
+                .accounts
+                .hook_freeze_authority
+                .as_ref()
+                .ok_or(AmmError::MissingTransferHookAccounts)?;
+            let hook_rule_authority = ctx
+                .accounts
+                .hook_rule_authority
+                .as_ref()
+                .ok_or(AmmError::MissingTransferHookAccounts)?;
+            
+            transfer_hook::cpi::revoke_freeze_authority(CpiContext::new(
+                transfer_hook_program.to_account_info(),
+                transfer_hook::cpi::accounts::RevokeFreezeAuthority {
+                    authority: ctx.accounts.authority.to_account_info(),
+                    mint: mint.to_account_info(),
+                    rule_authority: hook_rule_authority.to_account_info(),
+                    freeze_authority: hook_freeze_authority.to_account_info(),
+                    token_program: ctx.accounts.token_program.to_account_info(),
+                },
+            ))?;
+            msg!("Revoked freeze authority of mint: {}", mint_key);
+        }
+        
+        if revoke_mint_authority {
+            token_2022::set_authority(
+                CpiContext::new_with_signer(
+                    ctx.accounts.token_program.to_account_info(),
+                    token_2022::SetAuthority {
+                        current_authority: ctx.accounts.mint_authority.to_account_info(),
+                        account_or_mint: mint.to_account_info(),
+                    },
+                    &[mint_authority_seeds],
+                ),
+                AuthorityType::MintTokens,
+                None,
+            )?;
+            msg!("Revoked mint authority of mint: {}, supply fixed at {}", mint_key, mint.supply);
+        }
+        
+        Ok(())
+    }
+
+    /// Create a group mint that `create_token_with_hook` can add member mints
+    /// to, up to `max_size` members. The payer is the group's update authority.
+    pub fn create_token_group(ctx: Context<CreateTokenGroup>, max_size: u64) -> Result<()> {
+        let group_mint = ctx.accounts.group_mint.to_account_info();
+        let token_program = ctx.accounts.token_program.to_account_info();
+        let payer = ctx.accounts.payer.key();
+        
+        let space = ExtensionType::try_calculate_account_len::<Token2022Mint>(&[
+            ExtensionType::GroupPointer,
+        ])?;
+        // Token-2022 grows the mint to fit the group itself
+        let allocated_space = ExtensionType::try_calculate_account_len::<Token2022Mint>(&[
+            ExtensionType::GroupPointer,
+            ExtensionType::TokenGroup,
+        ])?;
+        system_program::create_account(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                system_program::CreateAccount {
+                    from: ctx.accounts.payer.to_account_info(),
+                    to: group_mint.clone(),
+                },
+            ),
+            Rent::get()?.minimum_balance(allocated_space),
+            space as u64,
+            &token_2022::ID,
+        )?;
+        
+        group_pointer_initialize(
+            CpiContext::new(
+                token_program.clone(),
+                GroupPointerInitialize {
+                    token_program_id: token_program.clone(),
+                    mint: group_mint.clone(),
+                },
+            ),
+            Some(payer),
+            Some(group_mint.key()),
+        )?;
+        token_2022::initialize_mint2(
+            CpiContext::new(
+                token_program.clone(),
+                token_2022::InitializeMint2 {
+                    mint: group_mint.clone(),
+                },
+            ),
+            0,
+            &payer,
+            None,
+        )?;
+        token_group_initialize(
+            CpiContext::new(
+                token_program.clone(),
+                TokenGroupInitialize {
+                    program_id: token_program,
+                    group: group_mint.clone(),
+                    mint: group_mint.clone(),
+                    mint_authority: ctx.accounts.payer.to_account_info(),
+                },
+            ),
+            Some(payer),
+            max_size,
+        )?;
+        
+        msg!("Created token group {} with max size {}", group_mint.key(), max_size);
+        
+        Ok(())
+    }
+
+    /// Set a metadata field of a mint created by `create_token_with_hook`.
+    /// `name`, `symbol` and `uri` update the base fields; any other key
+    /// sets an additional field.
+    pub fn update_token_metadata_field(
+        ctx: Context<UpdateTokenMetadata>,
+        field: String,
+        value: String,
+    ) -> Result<()> {
+        let mint_info = ctx.accounts.mint.to_account_info();
+        let field = match field.as_str() {
+            "name" => Field::Name,
+            "symbol" => Field::Symbol,
+            "uri" => Field::Uri,
+            _ => Field::Key(field),
+        };
+        
+        // Top up the rent for a longer value before Token-2022 grows the mint
+        let (old_size, new_size) = {
+            let data = mint_info.try_borrow_data()?;
+            let state = StateWithExtensions::<Token2022Mint>::unpack(&data)?;
+            let mut metadata = state.get_variable_len_extension::<TokenMetadata>()?;
+            let old_size = metadata.tlv_size_of()?;
+            metadata.update(field.clone(), value.clone());
+            (old_size, metadata.tlv_size_of()?)
+        };
+        if new_size > old_size {
+            let required = Rent::get()?
+                .minimum_balance(mint_info.data_len() + new_size - old_size)
+                .saturating_sub(mint_info.lamports());
+            if required > 0 {
+                system_program::transfer(
+                    CpiContext::new(
+                        ctx.accounts.system_program.to_account_info(),
+                        system_program::Transfer {
+                            from: ctx.accounts.update_authority.to_account_info(),
+                            to: mint_info.clone(),
+                        },
+                    ),
+                    required,
+                )?;
+            }
+        }
+        
+        token_metadata_update_field(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                TokenMetadataUpdateField {
+                    program_id: ctx.accounts.token_program.to_account_info(),
+                    metadata: mint_info,
+                    update_authority: ctx.accounts.update_authority.to_account_info(),
+                },
+            ),
+            field,
+            value,
+        )?;
+        
+        msg!("Updated metadata of mint: {}", ctx.accounts.mint.key());
+        
+        Ok(())
+    }
+
+    /// Hand the metadata update authority to `new_authority`, or make the
+    /// metadata immutable with `None`
+    pub fn update_token_metadata_authority(
+        ctx: Context<UpdateTokenMetadata>,
+        new_authority: Option<Pubkey>,
+    ) -> Result<()> {
+        let update_authority = ctx.accounts.update_authority.to_account_info();
+        
+        token_metadata_update_authority(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                TokenMetadataUpdateAuthority {
+                    program_id: ctx.accounts.token_program.to_account_info(),
+                    metadata: ctx.accounts.mint.to_account_info(),
+                    current_authority: update_authority.clone(),
+                    new_authority: update_authority,
+                },
+            ),
+            OptionalNonZeroPubkey::try_from(new_authority)?,
+        )?;
+        
+        msg!("Updated metadata authority of mint: {}", ctx.accounts.mint.key());
+        
+        Ok(())
+    }
+
+    /// Move tokens out of a lost or sanctioned wallet. The recovery authority
+    /// stages the transfer in the hook, then this program executes it as the
+    /// mint's permanent delegate. The remaining accounts are the extra
+    /// accounts required by the mint's transfer hook.
+    pub fn force_transfer<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ForceTransfer<'info>>,
+        amount: u64,
+    ) -> Result<()> {
+        let mint = &ctx.accounts.mint;
+        let source = &ctx.accounts.source_token;
+        let destination = &ctx.accounts.destination_token;
+        
+        transfer_hook::cpi::authorize_forced_transfer(
+            CpiContext::new(
+                ctx.accounts.transfer_hook_program.to_account_info(),
+                transfer_hook::cpi::accounts::AuthorizeForcedTransfer {
+                    authority: ctx.accounts.recovery_authority.to_account_info(),
+                    mint: mint.to_account_info(),
+                    recovery_config: ctx.accounts.recovery_config.to_account_info(),
+                },
+            ),
+            source.key(),
+            destination.key(),
+            amount,
+        )?;
+        
+        let mint_key = mint.key();
+        let delegate_seeds = &[
+            b"permanent-delegate",
+            mint_key.as_ref(),
+            &[ctx.bumps.permanent_delegate],
+        ];
+        onchain::invoke_transfer_checked(
+            &token_2022::ID,
+            source.to_account_info(),
+            mint.to_account_info(),
+            destination.to_account_info(),
+            ctx.accounts.permanent_delegate.to_account_info(),
+            ctx.remaining_accounts,
+            amount,
+            mint.decimals,
+            &[delegate_seeds],
+        )?;
+        
+        msg!("Forced transfer of {} tokens from {} to {}", amount, source.key(), destination.key());
+        
+        Ok(())
+    }
+
+    /// Settle sell tax owed by the owner of `seller_token` on a mint created
+    /// with `extensions.recovery`, whose permanent delegate only signs through
+    /// this program. The mint's tax collector signs and receives `amount`,
+    /// which the hook deducts from the seller's tax ledger and refuses if it
+    /// exceeds what is owed. The collector has to be able to receive the mint
+    /// under its other rules. The remaining accounts are the extra accounts
+    /// required by the mint's transfer hook.
+    pub fn settle_sell_tax<'info>(
+        ctx: Context<'_, '_, 'info, 'info, SettleSellTax<'info>>,
+        amount: u64,
+    ) -> Result<()> {
+        let mint = &ctx.accounts.mint;
+        let seller_token = &ctx.accounts.seller_token;
+        
+        let sell_tax_config = transfer_hook::SellTaxConfig::try_deserialize(
+            &mut &ctx.accounts.sell_tax_config.try_borrow_data()?[..],
+        )?;
+        require_keys_eq!(
+            sell_tax_config.collector,
+            ctx.accounts.collector.key(),
+            AmmError::NotTaxCollector
+        );
+        
+        let mint_key = mint.key();
+        let delegate_seeds = &[
+            b"permanent-delegate",
+            mint_key.as_ref(),
+            &[ctx.bumps.permanent_delegate],
+        ];
+        onchain::invoke_transfer_checked(
+            &token_2022::ID,
+            seller_token.to_account_info(),
+            mint.to_account_info(),
+            ctx.accounts.collector_token.to_account_info(),
+            ctx.accounts.permanent_delegate.to_account_info(),
+            ctx.remaining_accounts,
+            amount,
+            mint.decimals,
+            &[delegate_seeds],
+        )?;
+        
+        msg!("Settled {} sell tax owed by {}", amount, seller_token.owner);
+        
+        Ok(())
+    }
+
+    /// Point an existing mint's TransferHook extension at `transfer_hook_program`.
+    /// The signer must be the extension's authority.
     pub fn initialize_transfer_hook(
         ctx: Context<InitializeTransferHook>,
     ) -> Result<()> {
@@ -278,15 +1165,66 @@ pub mod token2022_amm {
         msg!("Initializing transfer hook for mint: {}", mint.key());
         msg!("Transfer Hook Program: {}", transfer_hook_program.key());
         
-        // This would typically involve setting up the transfer hook extension
-        // on the mint account. For now, we'll just log the action.
+        let extension = get_mint_extension_data::<TransferHookExtension>(&mint.to_account_info())
+            .map_err(|_| AmmError::MissingTransferHookExtension)?;
+        require!(
+            Option::<Pubkey>::from(extension.authority) == Some(ctx.accounts.authority.key()),
+            AmmError::InvalidTransferHookAuthority
+        );
+        
+        transfer_hook_update(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                TransferHookUpdate {
+                    token_program_id: ctx.accounts.token_program.to_account_info(),
+                    mint: mint.to_account_info(),
+                    authority: ctx.accounts.authority.to_account_info(),
+                },
+            ),
+            Some(transfer_hook_program.key()),
+        )?;
+        
+        // Other hook programs have to initialize their own meta list. A mint
+        // switching back to the transfer_hook program keeps its existing one.
+        if transfer_hook_program.key() == transfer_hook::ID {
+            let extra_account_meta_list = ctx
+                .accounts
+                .extra_account_meta_list
+                .as_ref()
+                .ok_or(AmmError::MissingTransferHookAccounts)?;
+            let hook_sanctions_config = ctx
+                .accounts
+                .hook_sanctions_config
+                .as_ref()
+                .ok_or(AmmError::MissingTransferHookAccounts)?;
+            let hook_recovery_config = ctx
+                .accounts
+                .hook_recovery_config
+                .as_ref()
+                .ok_or(AmmError::MissingTransferHookAccounts)?;
+            
+            if extra_account_meta_list.data_is_empty() {
+                transfer_hook::cpi::initialize_extra_account_meta_list(CpiContext::new(
+                    transfer_hook_program.to_account_info(),
+                    transfer_hook::cpi::accounts::InitializeExtraAccountMetaList {
+                        payer: ctx.accounts.authority.to_account_info(),
+                        extra_account_meta_list: extra_account_meta_list.to_account_info(),
+                        mint: mint.to_account_info(),
+                        system_program: ctx.accounts.system_program.to_account_info(),
+                        sanctions_config: hook_sanctions_config.to_account_info(),
+                        recovery_config: hook_recovery_config.to_account_info(),
+                    },
+                ))?;
+            }
+        }
         
         Ok(())
     }
 
-    /// Add account to transfer hook whitelist
-    pub fn add_to_whitelist(
-        ctx: Context<ManageWhitelist>,
+    /// Add account to transfer hook whitelist. The remaining accounts are the
+    /// wallet's token accounts for the mint, which the hook thaws.
+    pub fn add_to_whitelist<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ManageWhitelist<'info>>,
         account_to_add: Pubkey,
     ) -> Result<()> {
         let authority = &ctx.accounts.authority;
@@ -294,15 +1232,32 @@ pub mod token2022_amm {
         msg!("Adding account {} to whitelist", account_to_add);
         msg!("Authority: {}", authority.key());
         
-        // This would typically interact with the transfer hook program
-        // to add the account to the whitelist
+        // The hook checks that the signer is the whitelist authority
+        transfer_hook::cpi::add_to_whitelist(
+            CpiContext::new(
+                ctx.accounts.transfer_hook_program.to_account_info(),
+                transfer_hook::cpi::accounts::AddToWhitelist {
+                    authority: authority.to_account_info(),
+                    mint: ctx.accounts.mint.to_account_info(),
+                    whitelist: ctx.accounts.whitelist.to_account_info(),
+                    new_account: ctx.accounts.wallet.to_account_info(),
+                    entry: ctx.accounts.entry.to_account_info(),
+                    whitelist_stats: ctx.accounts.whitelist_stats.to_account_info(),
+                    freeze_authority: ctx.accounts.freeze_authority.to_account_info(),
+                    token_program: ctx.accounts.token_program.to_account_info(),
+                    system_program: ctx.accounts.system_program.to_account_info(),
+                },
+            )
+            .with_remaining_accounts(ctx.remaining_accounts.to_vec()),
+        )?;
         
         Ok(())
     }
 
-    /// Remove account from transfer hook whitelist
-    pub fn remove_from_whitelist(
-        ctx: Context<ManageWhitelist>,
+    /// Remove account from transfer hook whitelist. The remaining accounts are
+    /// the wallet's token accounts for the mint, which the hook freezes.
+    pub fn remove_from_whitelist<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ManageWhitelist<'info>>,
         account_to_remove: Pubkey,
     ) -> Result<()> {
         let authority = &ctx.accounts.authority;
@@ -310,23 +1265,53 @@ pub mod token2022_amm {
         msg!("Removing account {} from whitelist", account_to_remove);
         msg!("Authority: {}", authority.key());
         
-        // This would typically interact with the transfer hook program
-        // to remove the account from the whitelist
+        // The hook checks that the signer is the whitelist authority
+        transfer_hook::cpi::remove_from_whitelist(
+            CpiContext::new(
+                ctx.accounts.transfer_hook_program.to_account_info(),
+                transfer_hook::cpi::accounts::RemoveFromWhitelist {
+                    authority: authority.to_account_info(),
+                    mint: ctx.accounts.mint.to_account_info(),
+                    whitelist: ctx.accounts.whitelist.to_account_info(),
+                    account_to_remove: ctx.accounts.wallet.to_account_info(),
+                    entry: ctx.accounts.entry.to_account_info(),
+                    whitelist_stats: ctx.accounts.whitelist_stats.to_account_info(),
+                    exemption: ctx.accounts.exemption.to_account_info(),
+                    freeze_authority: ctx.accounts.freeze_authority.to_account_info(),
+                    token_program: ctx.accounts.token_program.to_account_info(),
+                },
+            )
+            .with_remaining_accounts(ctx.remaining_accounts.to_vec()),
+        )?;
         
         Ok(())
     }
 
-    pub fn swap(
-        ctx: Context<Swap>,
+    /// Swap tokens in the pool. The remaining accounts are one combined list
+    /// of the extra accounts required by the transfer hooks of both mints, for
+    /// any hook program. Swaps of a mint with a sell tax into the pool open
+    /// the user's tax ledger, at the user's expense, if it does not exist yet.
+    pub fn swap<'info>(
+        ctx: Context<'_, '_, 'info, 'info, Swap<'info>>,
         amount_in: u64,
         amount_out_min: u64,
         a_to_b: bool, // true for A->B, false for B->A
     ) -> Result<()> {
         let pool = &mut ctx.accounts.pool;
+        let epoch = Clock::get()?.epoch;
+        let (mint_in, mint_out) = if a_to_b {
+            (&ctx.accounts.token_a_mint, &ctx.accounts.token_b_mint)
+        } else {
+            (&ctx.accounts.token_b_mint, &ctx.accounts.token_a_mint)
+        };
+        
+        // Price on what the vault actually receives after the input mint's
+        // transfer fee
+        let amount_in_received = amount_in - transfer_fee(mint_in, epoch, amount_in)?;
         
         let (amount_out, fee) = if a_to_b {
             // Swapping A for B
-            let amount_in_with_fee = amount_in
+            let amount_in_with_fee = amount_in_received
                 .checked_mul(10000 - pool.fee_rate)
                 .unwrap()
                 .checked_div(10000)
@@ -338,15 +1323,14 @@ pub mod token2022_amm {
                 .checked_div(pool.token_a_reserve + amount_in_with_fee)
                 .unwrap();
             
-            let fee = amount_in - amount_in_with_fee;
+            let fee = amount_in_received - amount_in_with_fee;
             
-            require!(amount_out >= amount_out_min, AmmError::InsufficientOutputAmount);
             require!(amount_out < pool.token_b_reserve, AmmError::InsufficientLiquidity);
             
             (amount_out, fee)
         } else {
             // Swapping B for A
-            let amount_in_with_fee = amount_in
+            let amount_in_with_fee = amount_in_received
                 .checked_mul(10000 - pool.fee_rate)
                 .unwrap()
                 .checked_div(10000)
@@ -358,26 +1342,37 @@ pub mod token2022_amm {
                 .checked_div(pool.token_b_reserve + amount_in_with_fee)
                 .unwrap();
             
-            let fee = amount_in - amount_in_with_fee;
+            let fee = amount_in_received - amount_in_with_fee;
             
-            require!(amount_out >= amount_out_min, AmmError::InsufficientOutputAmount);
             require!(amount_out < pool.token_a_reserve, AmmError::InsufficientLiquidity);
             
             (amount_out, fee)
         };
         
+        // The user receives the output minus the output mint's transfer fee
+        let amount_out_received = amount_out - transfer_fee(mint_out, epoch, amount_out)?;
+        require!(amount_out_received >= amount_out_min, AmmError::InsufficientOutputAmount);
+        
+        open_tax_ledger_if_missing(
+            mint_in,
+            &ctx.accounts.user,
+            &ctx.accounts.system_program,
+            ctx.remaining_accounts,
+        )?;
+        
         if a_to_b {
             // Transfer A from user to pool
             transfer_checked_with_hook_support(
                 CpiContext::new(
-                    ctx.accounts.token_program.to_account_info(),
+                    ctx.accounts.token_a_program.to_account_info(),
                     TransferChecked {
                         from: ctx.accounts.user_token_a.to_account_info(),
                         to: ctx.accounts.token_a_vault.to_account_info(),
                         authority: ctx.accounts.user.to_account_info(),
                         mint: ctx.accounts.token_a_mint.to_account_info(),
                     },
-                ),
+                )
+                .with_remaining_accounts(hook_accounts(&ctx.accounts.token_a_mint, ctx.remaining_accounts)?),
                 amount_in,
                 ctx.accounts.token_a_mint.decimals,
             )?;
@@ -392,7 +1387,7 @@ pub mod token2022_amm {
             
             transfer_checked_with_hook_support(
                 CpiContext::new_with_signer(
-                    ctx.accounts.token_program.to_account_info(),
+                    ctx.accounts.token_b_program.to_account_info(),
                     TransferChecked {
                         from: ctx.accounts.token_b_vault.to_account_info(),
                         to: ctx.accounts.user_token_b.to_account_info(),
@@ -400,26 +1395,28 @@ pub mod token2022_amm {
                         mint: ctx.accounts.token_b_mint.to_account_info(),
                     },
                     &[pool_seeds],
-                ),
+                )
+                .with_remaining_accounts(hook_accounts(&ctx.accounts.token_b_mint, ctx.remaining_accounts)?),
                 amount_out,
                 ctx.accounts.token_b_mint.decimals,
             )?;
             
             // Update reserves
-            pool.token_a_reserve += amount_in;
+            pool.token_a_reserve += amount_in_received;
             pool.token_b_reserve -= amount_out;
         } else {
             // Transfer B from user to pool
             transfer_checked_with_hook_support(
                 CpiContext::new(
-                    ctx.accounts.token_program.to_account_info(),
+                    ctx.accounts.token_b_program.to_account_info(),
                     TransferChecked {
                         from: ctx.accounts.user_token_b.to_account_info(),
                         to: ctx.accounts.token_b_vault.to_account_info(),
                         authority: ctx.accounts.user.to_account_info(),
                         mint: ctx.accounts.token_b_mint.to_account_info(),
                     },
-                ),
+                )
+                .with_remaining_accounts(hook_accounts(&ctx.accounts.token_b_mint, ctx.remaining_accounts)?),
                 amount_in,
                 ctx.accounts.token_b_mint.decimals,
             )?;
@@ -434,7 +1431,7 @@ pub mod token2022_amm {
             
             transfer_checked_with_hook_support(
                 CpiContext::new_with_signer(
-                    ctx.accounts.token_program.to_account_info(),
+                    ctx.accounts.token_a_program.to_account_info(),
                     TransferChecked {
                         from: ctx.accounts.token_a_vault.to_account_info(),
                         to: ctx.accounts.user_token_a.to_account_info(),
@@ -442,33 +1439,307 @@ pub mod token2022_amm {
                         mint: ctx.accounts.token_a_mint.to_account_info(),
                     },
                     &[pool_seeds],
-                ),
+                )
+                .with_remaining_accounts(hook_accounts(&ctx.accounts.token_a_mint, ctx.remaining_accounts)?),
                 amount_out,
                 ctx.accounts.token_a_mint.decimals,
             )?;
             
             // Update reserves
-            pool.token_b_reserve += amount_in;
+            pool.token_b_reserve += amount_in_received;
             pool.token_a_reserve -= amount_out;
         }
         
-        msg!("Swap completed: {} in, {} out, {} fee", amount_in, amount_out, fee);
+        msg!("Swap completed: {} in, {} out, {} fee", amount_in_received, amount_out_received, fee);
         
         Ok(())
     }
 }
 
+// Initialize the optional extensions selected for a new mint, before the
+// mint itself. Authorities of configurable extensions go to `payer`.
+fn initialize_selected_extensions<'info>(
+    extensions: &ExtensionConfig,
+    mint: &AccountInfo<'info>,
+    payer: &Pubkey,
+    token_program: &AccountInfo<'info>,
+    program_id: &Pubkey,
+) -> Result<()> {
+    if let Some(fee) = &extensions.transfer_fee {
+        transfer_fee_initialize(
+            CpiContext::new(
+                token_program.clone(),
+                TransferFeeInitialize {
+                    token_program_id: token_program.clone(),
+                    mint: mint.clone(),
+                },
+            ),
+            Some(payer),
+            Some(payer),
+            fee.basis_points,
+            fee.maximum_fee,
+        )?;
+    }
+    
+    if let Some(rate) = extensions.interest_rate {
+        interest_bearing_mint_initialize(
+            CpiContext::new(
+                token_program.clone(),
+                InterestBearingMintInitialize {
+                    token_program_id: token_program.clone(),
+                    mint: mint.clone(),
+                },
+            ),
+            Some(*payer),
+            rate,
+        )?;
+    }
+    
+    if extensions.default_account_frozen {
+        default_account_state_initialize(
+            CpiContext::new(
+                token_program.clone(),
+                DefaultAccountStateInitialize {
+                    token_program_id: token_program.clone(),
+                    mint: mint.clone(),
+                },
+            ),
+            &AccountState::Frozen,
+        )?;
+    }
+    
+    if extensions.confidential_transfers {
+        let ix = confidential_transfer::instruction::initialize_mint(
+            &token_2022::ID,
+            mint.key,
+            Some(*payer),
+            false,
+            None,
+        )?;
+        invoke(&ix, std::slice::from_ref(mint))?;
+    }
+    
+    // With recovery the permanent delegate is a PDA of this program, so
+    // forced transfers can only be executed through `force_transfer`
+    let permanent_delegate = if extensions.recovery {
+        Some(Pubkey::find_program_address(&[b"permanent-delegate", mint.key.as_ref()], program_id).0)
+    } else {
+        extensions.permanent_delegate
+    };
+    if let Some(permanent_delegate) = permanent_delegate {
+        permanent_delegate_initialize(
+            CpiContext::new(
+                token_program.clone(),
+                PermanentDelegateInitialize {
+                    token_program_id: token_program.clone(),
+                    mint: mint.clone(),
+                },
+            ),
+            &permanent_delegate,
+        )?;
+    }
+    
+    if extensions.non_transferable {
+        non_transferable_mint_initialize(CpiContext::new(
+            token_program.clone(),
+            NonTransferableMintInitialize {
+                token_program_id: token_program.clone(),
+                mint: mint.clone(),
+            },
+        ))?;
+    }
+    
+    if let Some(close_authority) = &extensions.close_authority {
+        mint_close_authority_initialize(
+            CpiContext::new(
+                token_program.clone(),
+                MintCloseAuthorityInitialize {
+                    token_program_id: token_program.clone(),
+                    mint: mint.clone(),
+                },
+            ),
+            Some(close_authority),
+        )?;
+    }
+    
+    if let Some(group_address) = extensions.group_pointer {
+        group_pointer_initialize(
+            CpiContext::new(
+                token_program.clone(),
+                GroupPointerInitialize {
+                    token_program_id: token_program.clone(),
+                    mint: mint.clone(),
+                },
+            ),
+            Some(*payer),
+            Some(group_address),
+        )?;
+    }
+    
+    Ok(())
+}
+
+// Check a pool mint's extensions against `policy`. NonTransferable mints and
+// mints whose accounts start frozen can never be held by the vaults, and
+// unknown extensions are rejected. Returns whether the mint is high-risk:
+// a permanent delegate can drain the vaults, a close authority can close the
+// mint, and an untrusted hook program can block transfers out of the pool.
+fn check_mint_extensions(mint: &InterfaceAccount<Mint>, policy: &ExtensionPolicy) -> Result<bool> {
+    let mint_info = mint.to_account_info();
+    let mint_data = mint_info.try_borrow_data()?;
+    let state = StateWithExtensions::<Token2022Mint>::unpack(&mint_data)?;
+    
+    let mut high_risk = false;
+    for extension_type in state.get_extension_types()? {
+        match extension_type {
+            ExtensionType::TransferFeeConfig
+            | ExtensionType::InterestBearingConfig
+            | ExtensionType::ConfidentialTransferMint
+            | ExtensionType::ConfidentialTransferFeeConfig
+            | ExtensionType::MetadataPointer
+            | ExtensionType::TokenMetadata
+            | ExtensionType::GroupPointer
+            | ExtensionType::TokenGroup
+            | ExtensionType::GroupMemberPointer
+            | ExtensionType::TokenGroupMember => {}
+            ExtensionType::DefaultAccountState => {
+                let default_state = state.get_extension::<DefaultAccountState>()?;
+                require!(
+                    default_state.state != AccountState::Frozen as u8,
+                    AmmError::UnsupportedMintExtension
+                );
+            }
+            ExtensionType::TransferHook => {
+                let hook_program_id: Option<Pubkey> =
+                    state.get_extension::<TransferHookExtension>()?.program_id.into();
+                if let Some(hook_program_id) = hook_program_id {
+                    high_risk |= hook_program_id != transfer_hook::ID
+                        && !policy.trusted_hook_programs.contains(&hook_program_id);
+                }
+            }
+            ExtensionType::PermanentDelegate | ExtensionType::MintCloseAuthority => {
+                high_risk = true;
+            }
+            _ => return err!(AmmError::UnsupportedMintExtension),
+        }
+    }
+    
+    Ok(high_risk)
+}
+
+// Transfer hook program configured on a Token-2022 mint, if any
+fn transfer_hook_program_id(mint: &InterfaceAccount<Mint>) -> Option<Pubkey> {
+    get_mint_extension_data::<TransferHookExtension>(&mint.to_account_info())
+        .ok()
+        .and_then(|extension| extension.program_id.into())
+}
+
+// Transfer fee Token-2022 withholds from a transfer of `amount` of `mint`
+// in `epoch`, zero for mints without a TransferFeeConfig
+fn transfer_fee(mint: &InterfaceAccount<Mint>, epoch: u64, amount: u64) -> Result<u64> {
+    let config = get_mint_extension_data::<TransferFeeConfig>(&mint.to_account_info()).ok();
+    epoch_fee(config.as_ref(), epoch, amount)
+}
+
+// Fee withheld under `config` from a transfer of `amount` in `epoch`
+fn epoch_fee(config: Option<&TransferFeeConfig>, epoch: u64, amount: u64) -> Result<u64> {
+    match config {
+        Some(config) => Ok(config
+            .calculate_epoch_fee(epoch, amount)
+            .ok_or(AmmError::TransferFeeCalculationFailed)?),
+        None => Ok(0),
+    }
+}
+
+// Remaining accounts to forward with a transfer of `mint`. A hooked mint needs
+// its hook program and ExtraAccountMetaList among them; the extra accounts
+// the list names are then picked out by key when the transfer is invoked.
+fn hook_accounts<'info>(
+    mint: &InterfaceAccount<'info, Mint>,
+    remaining_accounts: &[AccountInfo<'info>],
+) -> Result<Vec<AccountInfo<'info>>> {
+    let Some(hook_program_id) = transfer_hook_program_id(mint) else {
+        return Ok(Vec::new());
+    };
+    let (extra_account_meta_list, _) = Pubkey::find_program_address(
+        &[b"extra-account-metas", mint.key().as_ref()],
+        &hook_program_id,
+    );
+    let has = |key: &Pubkey| remaining_accounts.iter().any(|account| account.key == key);
+    require!(
+        has(&hook_program_id) && has(&extra_account_meta_list),
+        AmmError::MissingTransferHookAccounts
+    );
+    Ok(remaining_accounts.to_vec())
+}
+
+// The hook taxes transfers of a mint with a sell tax into pool vaults and
+// refuses them while the sender has no tax ledger, which it cannot create
+// during a transfer. Open the user's ledger, paid by the user, beforehand.
+fn open_tax_ledger_if_missing<'info>(
+    mint: &InterfaceAccount<'info, Mint>,
+    user: &Signer<'info>,
+    system_program: &Program<'info, System>,
+    remaining_accounts: &[AccountInfo<'info>],
+) -> Result<()> {
+    if transfer_hook_program_id(mint) != Some(transfer_hook::ID) {
+        return Ok(());
+    }
+    let mint_key = mint.key();
+    let user_key = user.key();
+    let find = |seeds: &[&[u8]]| {
+        let (address, _) = Pubkey::find_program_address(seeds, &transfer_hook::ID);
+        remaining_accounts.iter().find(|account| account.key == &address)
+    };
+    let Some(tax_ledger) = find(&[b"tax-ledger".as_ref(), mint_key.as_ref(), user_key.as_ref()])
+    else {
+        return Ok(());
+    };
+    let taxed = find(&[b"sell-tax".as_ref(), mint_key.as_ref()])
+        .is_some_and(|sell_tax_config| !sell_tax_config.data_is_empty());
+    if !taxed || !tax_ledger.data_is_empty() {
+        return Ok(());
+    }
+    let transfer_hook_program = remaining_accounts
+        .iter()
+        .find(|account| account.key == &transfer_hook::ID)
+        .ok_or(AmmError::MissingTransferHookAccounts)?;
+    
+    transfer_hook::cpi::open_tax_ledger(CpiContext::new(
+        transfer_hook_program.clone(),
+        transfer_hook::cpi::accounts::OpenTaxLedger {
+            payer: user.to_account_info(),
+            mint: mint.to_account_info(),
+            wallet: user.to_account_info(),
+            tax_ledger: tax_ledger.clone(),
+            system_program: system_program.to_account_info(),
+        },
+    ))?;
+    Ok(())
+}
+
 // Helper function to handle transfers with potential transfer hooks
 fn transfer_checked_with_hook_support<'info>(
     ctx: CpiContext<'_, '_, '_, 'info, TransferChecked<'info>>,
     amount: u64,
     decimals: u8,
 ) -> Result<()> {
-    // For Token-2022 with transfer hooks, we use transfer_checked which automatically
-    // invokes any transfer hooks configured on the mint
-    // The transfer hook program will validate the transfer based on its logic
-    // (e.g., whitelist validation, KYC checks, etc.)
-    transfer_checked(ctx, amount, decimals)
+    // Token-2022 only passes the hook the accounts given to the transfer, so
+    // the hook program, the mint's ExtraAccountMetaList and the extra accounts
+    // it lists are resolved from the remaining accounts and forwarded.
+    // Mints without a transfer hook ignore them.
+    onchain::invoke_transfer_checked(
+        ctx.program.key,
+        ctx.accounts.from,
+        ctx.accounts.mint,
+        ctx.accounts.to,
+        ctx.accounts.authority,
+        &ctx.remaining_accounts,
+        amount,
+        decimals,
+        ctx.signer_seeds,
+    )?;
+    Ok(())
 }
 
 // Account validation structs
@@ -477,21 +1748,221 @@ pub struct CreateTokenWithHook<'info> {
     #[account(mut)]
     pub payer: Signer<'info>,
     
+    /// New mint keypair, created with its extensions by the instruction
+    #[account(mut)]
+    pub mint: Signer<'info>,
+    
+    /// CHECK: Transfer hook program that will handle transfer validation
+    pub transfer_hook_program: UncheckedAccount<'info>,
+    
+    /// Supply cap of the mint, required exactly when `max_supply` is set
     #[account(
         init,
         payer = payer,
-        mint::decimals = 9,
-        mint::authority = payer,
-        mint::freeze_authority = payer,
+        space = SupplyConfig::SPACE,
+        seeds = [b"supply-config", mint.key().as_ref()],
+        bump
     )]
-    pub mint: InterfaceAccount<'info, Mint>,
+    pub supply_config: Option<Account<'info, SupplyConfig>>,
     
-    /// CHECK: Transfer hook program that will handle transfer validation
-    pub transfer_hook_program: UncheckedAccount<'info>,
+    /// CHECK: Payer's associated token account for the mint, created by the instruction
+    #[account(
+        mut,
+        address = get_associated_token_address_with_program_id(
+            &payer.key(),
+            &mint.key(),
+            &token_2022::ID,
+        ),
+    )]
+    pub payer_token_account: UncheckedAccount<'info>,
     
     pub token_program: Program<'info, Token2022>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
     pub system_program: Program<'info, System>,
     pub rent: Sysvar<'info, Rent>,
+    
+    // Required when creating a member of a token group
+    /// CHECK: Group mint created by `create_token_group`, validated by Token-2022
+    #[account(mut, owner = token_2022::ID)]
+    pub group_mint: Option<UncheckedAccount<'info>>,
+    
+    // Required when using the transfer_hook program
+    /// CHECK: Extra account meta list of the mint, created by the transfer hook program
+    #[account(mut)]
+    pub extra_account_meta_list: Option<UncheckedAccount<'info>>,
+    
+    /// CHECK: Sanctions config of the mint, validated by the transfer hook program
+    pub hook_sanctions_config: Option<UncheckedAccount<'info>>,
+    
+    /// CHECK: Recovery config of the mint, validated by the transfer hook program
+    pub hook_recovery_config: Option<UncheckedAccount<'info>>,
+    
+    // Required when enabling confidential transfers with the transfer_hook program
+    /// CHECK: Transfer limits of the mint, validated by the transfer hook program
+    pub hook_transfer_limits: Option<UncheckedAccount<'info>>,
+    
+    /// CHECK: Sell tax config of the mint, validated by the transfer hook program
+    pub hook_sell_tax_config: Option<UncheckedAccount<'info>>,
+    
+    /// CHECK: Confidential transfer policy of the mint, created by the transfer hook program
+    #[account(mut)]
+    pub confidential_policy: Option<UncheckedAccount<'info>>,
+    
+    // Required with the transfer_hook program when capping supply or enabling confidential transfers
+    /// CHECK: Rule authority of the mint, created by the transfer hook program when capping supply
+    #[account(mut)]
+    pub hook_rule_authority: Option<UncheckedAccount<'info>>,
+}
+
+#[derive(Accounts)]
+pub struct CreateTokenGroup<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    /// New group mint keypair, created by the instruction
+    #[account(mut)]
+    pub group_mint: Signer<'info>,
+    
+    pub token_program: Program<'info, Token2022>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct MintMore<'info> {
+    pub authority: Signer<'info>,
+    
+    #[account(
+        seeds = [b"supply-config", mint.key().as_ref()],
+        bump = supply_config.bump,
+        has_one = authority,
+        has_one = mint,
+    )]
+    pub supply_config: Account<'info, SupplyConfig>,
+    
+    #[account(mut)]
+    pub mint: InterfaceAccount<'info, Mint>,
+    
+    /// CHECK: PDA mint authority of capped tokens
+    #[account(
+        seeds = [b"mint-authority", mint.key().as_ref()],
+        bump
+    )]
+    pub mint_authority: UncheckedAccount<'info>,
+    
+    #[account(mut, token::mint = mint)]
+    pub destination: InterfaceAccount<'info, TokenAccount>,
+    
+    pub token_program: Program<'info, Token2022>,
+}
+
+#[derive(Accounts)]
+pub struct RevokeAuthorities<'info> {
+    pub authority: Signer<'info>,
+    
+    #[account(
+        seeds = [b"supply-config", mint.key().as_ref()],
+        bump = supply_config.bump,
+        has_one = authority,
+        has_one = mint,
+    )]
+    pub supply_config: Account<'info, SupplyConfig>,
+    
+    #[account(mut)]
+    pub mint: InterfaceAccount<'info, Mint>,
+    
+    /// CHECK: PDA mint authority of capped tokens
+    #[account(
+        seeds = [b"mint-authority", mint.key().as_ref()],
+        bump
+    )]
+    pub mint_authority: UncheckedAccount<'info>,
+    
+    pub token_program: Program<'info, Token2022>,
+    
+    // Required when revoking the freeze authority held by the transfer_hook program
+    pub transfer_hook_program: Option<Program<'info, TransferHookProgram>>,
+    
+    /// CHECK: Freeze authority PDA of the transfer hook program, validated by it
+    pub hook_freeze_authority: Option<UncheckedAccount<'info>>,
+    
+    /// CHECK: Rule authority of the mint, validated by the transfer hook program
+    pub hook_rule_authority: Option<UncheckedAccount<'info>>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateTokenMetadata<'info> {
+    /// Metadata update authority, also pays for a larger mint account
+    #[account(mut)]
+    pub update_authority: Signer<'info>,
+    
+    #[account(mut)]
+    pub mint: InterfaceAccount<'info, Mint>,
+    
+    pub token_program: Program<'info, Token2022>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ForceTransfer<'info> {
+    pub recovery_authority: Signer<'info>,
+    
+    pub mint: InterfaceAccount<'info, Mint>,
+    
+    #[account(mut, token::mint = mint)]
+    pub source_token: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(mut, token::mint = mint)]
+    pub destination_token: InterfaceAccount<'info, TokenAccount>,
+    
+    /// CHECK: PDA set as the mint's permanent delegate by `create_token_with_hook`
+    #[account(
+        seeds = [b"permanent-delegate", mint.key().as_ref()],
+        bump
+    )]
+    pub permanent_delegate: UncheckedAccount<'info>,
+    
+    /// CHECK: Recovery config of the mint, validated by the transfer hook program
+    #[account(
+        mut,
+        seeds = [b"recovery", mint.key().as_ref()],
+        bump,
+        seeds::program = transfer_hook_program.key(),
+    )]
+    pub recovery_config: UncheckedAccount<'info>,
+    
+    pub transfer_hook_program: Program<'info, TransferHookProgram>,
+    pub token_program: Program<'info, Token2022>,
+}
+
+#[derive(Accounts)]
+pub struct SettleSellTax<'info> {
+    pub collector: Signer<'info>,
+    
+    pub mint: InterfaceAccount<'info, Mint>,
+    
+    /// CHECK: Sell tax config of the mint, deserialized by the instruction
+    #[account(
+        seeds = [b"sell-tax", mint.key().as_ref()],
+        bump,
+        seeds::program = transfer_hook_program.key(),
+    )]
+    pub sell_tax_config: UncheckedAccount<'info>,
+    
+    #[account(mut, token::mint = mint)]
+    pub seller_token: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(mut, token::mint = mint, token::authority = collector)]
+    pub collector_token: InterfaceAccount<'info, TokenAccount>,
+    
+    /// CHECK: PDA set as the mint's permanent delegate by `create_token_with_hook`
+    #[account(
+        seeds = [b"permanent-delegate", mint.key().as_ref()],
+        bump
+    )]
+    pub permanent_delegate: UncheckedAccount<'info>,
+    
+    pub transfer_hook_program: Program<'info, TransferHookProgram>,
+    pub token_program: Program<'info, Token2022>,
 }
 
 #[derive(Accounts)]
@@ -503,25 +1974,79 @@ pub struct InitializeTransferHook<'info> {
     pub mint: InterfaceAccount<'info, Mint>,
     
     /// CHECK: Transfer hook program that will handle transfer validation
+    #[account(executable)]
     pub transfer_hook_program: UncheckedAccount<'info>,
     
     pub token_program: Program<'info, Token2022>,
+    pub system_program: Program<'info, System>,
+    
+    // Required when switching to the transfer_hook program
+    /// CHECK: Extra account meta list of the mint, created by the transfer hook program
+    #[account(mut)]
+    pub extra_account_meta_list: Option<UncheckedAccount<'info>>,
+    
+    /// CHECK: Sanctions config of the mint, validated by the transfer hook program
+    pub hook_sanctions_config: Option<UncheckedAccount<'info>>,
+    
+    /// CHECK: Recovery config of the mint, validated by the transfer hook program
+    pub hook_recovery_config: Option<UncheckedAccount<'info>>,
 }
 
 #[derive(Accounts)]
+#[instruction(account: Pubkey)]
 pub struct ManageWhitelist<'info> {
+    /// Whitelist authority, also pays for new whitelist entries
     #[account(mut)]
     pub authority: Signer<'info>,
     
-    /// CHECK: Transfer hook program that will handle whitelist management
-    pub transfer_hook_program: UncheckedAccount<'info>,
+    pub transfer_hook_program: Program<'info, TransferHookProgram>,
+    
+    /// Mint whose whitelist is managed
+    pub mint: InterfaceAccount<'info, Mint>,
     
     /// CHECK: Whitelist account managed by the transfer hook program
     #[account(
-        seeds = [b"whitelist"],
+        seeds = [b"whitelist", mint.key().as_ref()],
         bump,
+        seeds::program = transfer_hook_program.key(),
     )]
     pub whitelist: UncheckedAccount<'info>,
+    
+    /// CHECK: Wallet being added or removed
+    #[account(address = account)]
+    pub wallet: UncheckedAccount<'info>,
+    
+    /// CHECK: Whitelist entry of the wallet, created or closed by the transfer hook program
+    #[account(
+        mut,
+        seeds = [b"whitelist-entry", mint.key().as_ref(), wallet.key().as_ref()],
+        bump,
+        seeds::program = transfer_hook_program.key(),
+    )]
+    pub entry: UncheckedAccount<'info>,
+    
+    /// CHECK: Whitelist member count, updated by the transfer hook program
+    #[account(
+        mut,
+        seeds = [b"whitelist-stats", mint.key().as_ref()],
+        bump,
+        seeds::program = transfer_hook_program.key(),
+    )]
+    pub whitelist_stats: UncheckedAccount<'info>,
+    
+    /// CHECK: Exemption of the wallet, read by the transfer hook program
+    #[account(
+        seeds = [b"exempt-authority", wallet.key().as_ref()],
+        bump,
+        seeds::program = transfer_hook_program.key(),
+    )]
+    pub exemption: UncheckedAccount<'info>,
+    
+    /// CHECK: Freeze authority PDA of the transfer hook program, validated by it
+    pub freeze_authority: UncheckedAccount<'info>,
+    
+    pub token_program: Program<'info, Token2022>,
+    pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
@@ -538,7 +2063,9 @@ pub struct InitializePool<'info> {
     )]
     pub pool: Account<'info, Pool>,
     
+    #[account(mint::token_program = token_a_program)]
     pub token_a_mint: InterfaceAccount<'info, Mint>,
+    #[account(mint::token_program = token_b_program)]
     pub token_b_mint: InterfaceAccount<'info, Mint>,
     
     #[account(
@@ -548,6 +2075,7 @@ pub struct InitializePool<'info> {
         bump,
         token::mint = token_a_mint,
         token::authority = pool,
+        token::token_program = token_a_program,
     )]
     pub token_a_vault: InterfaceAccount<'info, TokenAccount>,
     
@@ -558,6 +2086,7 @@ pub struct InitializePool<'info> {
         bump,
         token::mint = token_b_mint,
         token::authority = pool,
+        token::token_program = token_b_program,
     )]
     pub token_b_vault: InterfaceAccount<'info, TokenAccount>,
     
@@ -568,10 +2097,45 @@ pub struct InitializePool<'info> {
         bump,
         mint::decimals = 9,
         mint::authority = pool,
+        mint::token_program = lp_token_program,
     )]
     pub lp_token_mint: InterfaceAccount<'info, Mint>,
     
-    pub token_program: Program<'info, Token2022>,
+    pub token_a_program: Interface<'info, TokenInterface>,
+    pub token_b_program: Interface<'info, TokenInterface>,
+    pub lp_token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+    
+    // Required when either mint uses the transfer_hook program
+    /// CHECK: Trusted program registration for this AMM, validated by the transfer hook program
+    pub hook_trusted_program: Option<UncheckedAccount<'info>>,
+    
+    /// CHECK: Exemption record for the pool PDA, created by the transfer hook program
+    #[account(mut)]
+    pub pool_exemption: Option<UncheckedAccount<'info>>,
+    
+    pub transfer_hook_program: Option<Program<'info, TransferHookProgram>>,
+}
+
+#[derive(Accounts)]
+pub struct MigratePool<'info> {
+    /// Pays for the larger pool account
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    /// CHECK: Pool in the layout before version 1, validated by the instruction
+    #[account(
+        mut,
+        owner = crate::ID,
+        seeds = [b"pool", token_a_mint.key().as_ref(), token_b_mint.key().as_ref()],
+        bump
+    )]
+    pub pool: UncheckedAccount<'info>,
+    
+    pub token_a_mint: InterfaceAccount<'info, Mint>,
+    pub token_b_mint: InterfaceAccount<'info, Mint>,
+    pub lp_token_mint: InterfaceAccount<'info, Mint>,
+    
     pub system_program: Program<'info, System>,
 }
 
@@ -594,6 +2158,7 @@ pub struct AddLiquidity<'info> {
         mut,
         associated_token::mint = token_a_mint,
         associated_token::authority = user,
+        associated_token::token_program = token_a_program,
     )]
     pub user_token_a: InterfaceAccount<'info, TokenAccount>,
     
@@ -601,6 +2166,7 @@ pub struct AddLiquidity<'info> {
         mut,
         associated_token::mint = token_b_mint,
         associated_token::authority = user,
+        associated_token::token_program = token_b_program,
     )]
     pub user_token_b: InterfaceAccount<'info, TokenAccount>,
     
@@ -608,6 +2174,7 @@ pub struct AddLiquidity<'info> {
         mut,
         associated_token::mint = lp_token_mint,
         associated_token::authority = user,
+        associated_token::token_program = lp_token_program,
     )]
     pub user_lp_token: InterfaceAccount<'info, TokenAccount>,
     
@@ -632,7 +2199,13 @@ pub struct AddLiquidity<'info> {
     )]
     pub lp_token_mint: InterfaceAccount<'info, Mint>,
     
-    pub token_program: Program<'info, Token2022>,
+    #[account(address = pool.token_a_program)]
+    pub token_a_program: Interface<'info, TokenInterface>,
+    #[account(address = pool.token_b_program)]
+    pub token_b_program: Interface<'info, TokenInterface>,
+    #[account(address = pool.lp_token_program)]
+    pub lp_token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
@@ -654,6 +2227,7 @@ pub struct RemoveLiquidity<'info> {
         mut,
         associated_token::mint = token_a_mint,
         associated_token::authority = user,
+        associated_token::token_program = token_a_program,
     )]
     pub user_token_a: InterfaceAccount<'info, TokenAccount>,
     
@@ -661,6 +2235,7 @@ pub struct RemoveLiquidity<'info> {
         mut,
         associated_token::mint = token_b_mint,
         associated_token::authority = user,
+        associated_token::token_program = token_b_program,
     )]
     pub user_token_b: InterfaceAccount<'info, TokenAccount>,
     
@@ -668,6 +2243,7 @@ pub struct RemoveLiquidity<'info> {
         mut,
         associated_token::mint = lp_token_mint,
         associated_token::authority = user,
+        associated_token::token_program = lp_token_program,
     )]
     pub user_lp_token: InterfaceAccount<'info, TokenAccount>,
     
@@ -692,7 +2268,12 @@ pub struct RemoveLiquidity<'info> {
     )]
     pub lp_token_mint: InterfaceAccount<'info, Mint>,
     
-    pub token_program: Program<'info, Token2022>,
+    #[account(address = pool.token_a_program)]
+    pub token_a_program: Interface<'info, TokenInterface>,
+    #[account(address = pool.token_b_program)]
+    pub token_b_program: Interface<'info, TokenInterface>,
+    #[account(address = pool.lp_token_program)]
+    pub lp_token_program: Interface<'info, TokenInterface>,
 }
 
 #[derive(Accounts)]
@@ -714,6 +2295,7 @@ pub struct Swap<'info> {
         mut,
         associated_token::mint = token_a_mint,
         associated_token::authority = user,
+        associated_token::token_program = token_a_program,
     )]
     pub user_token_a: InterfaceAccount<'info, TokenAccount>,
     
@@ -721,6 +2303,7 @@ pub struct Swap<'info> {
         mut,
         associated_token::mint = token_b_mint,
         associated_token::authority = user,
+        associated_token::token_program = token_b_program,
     )]
     pub user_token_b: InterfaceAccount<'info, TokenAccount>,
     
@@ -738,54 +2321,92 @@ pub struct Swap<'info> {
     )]
     pub token_b_vault: InterfaceAccount<'info, TokenAccount>,
     
-    // Transfer hook accounts for Token-2022 validation
-    /// CHECK: Optional extra account meta list for token A transfer hooks
-    #[account(
-        seeds = [b"extra-account-metas", token_a_mint.key().as_ref()],
-        bump
-    )]
-    pub token_a_extra_account_meta_list: UncheckedAccount<'info>,
-    
-    /// CHECK: Optional extra account meta list for token B transfer hooks
-    #[account(
-        seeds = [b"extra-account-metas", token_b_mint.key().as_ref()],
-        bump
-    )]
-    pub token_b_extra_account_meta_list: UncheckedAccount<'info>,
-    
-    // Whitelist accounts for transfer hook validation
-    /// CHECK: Whitelist account for token A transfer hooks
-    #[account(
-        seeds = [b"whitelist"],
-        bump
-    )]
-    pub token_a_whitelist: UncheckedAccount<'info>,
-    
-    /// CHECK: Whitelist account for token B transfer hooks
-    #[account(
-        seeds = [b"whitelist"],
-        bump
-    )]
-    pub token_b_whitelist: UncheckedAccount<'info>,
-    
-    /// CHECK: Hook authority for token A
-    #[account(
-        seeds = [b"hook_authority"],
-        bump
-    )]
-    pub token_a_hook_authority: UncheckedAccount<'info>,
-    
-    /// CHECK: Hook authority for token B
-    #[account(
-        seeds = [b"hook_authority"],
-        bump
-    )]
-    pub token_b_hook_authority: UncheckedAccount<'info>,
-    
-    pub token_program: Program<'info, Token2022>,
+    #[account(address = pool.token_a_program)]
+    pub token_a_program: Interface<'info, TokenInterface>,
+    #[account(address = pool.token_b_program)]
+    pub token_b_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+// Which mint extensions `initialize_pool` accepts beyond the always-supported
+// ones. Our own transfer hook program is always trusted.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Default)]
+pub struct ExtensionPolicy {
+    pub allow_high_risk: bool, // Accept high-risk mints and flag the pool
+    pub trusted_hook_programs: Vec<Pubkey>,
+}
+
+// Optional extensions of a mint created by `create_token_with_hook`, on top
+// of the TransferHook and MetadataPointer extensions every such mint has.
+// Incompatible combinations are rejected by Token-2022.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Default)]
+pub struct ExtensionConfig {
+    pub transfer_fee: Option<TransferFeeArgs>,
+    pub interest_rate: Option<i16>, // In basis points
+    pub default_account_frozen: bool,
+    pub confidential_transfers: bool,
+    pub recovery: bool, // Permanent delegate PDA used by `force_transfer`
+    pub permanent_delegate: Option<Pubkey>,
+    pub non_transferable: bool,
+    pub close_authority: Option<Pubkey>,
+    pub group_pointer: Option<Pubkey>, // Group account address
+}
+
+impl ExtensionConfig {
+    pub fn extension_types(&self) -> Vec<ExtensionType> {
+        let mut extension_types = Vec::new();
+        if self.transfer_fee.is_some() {
+            extension_types.push(ExtensionType::TransferFeeConfig);
+        }
+        if self.interest_rate.is_some() {
+            extension_types.push(ExtensionType::InterestBearingConfig);
+        }
+        if self.default_account_frozen {
+            extension_types.push(ExtensionType::DefaultAccountState);
+        }
+        if self.confidential_transfers {
+            extension_types.push(ExtensionType::ConfidentialTransferMint);
+        }
+        if self.recovery || self.permanent_delegate.is_some() {
+            extension_types.push(ExtensionType::PermanentDelegate);
+        }
+        if self.non_transferable {
+            extension_types.push(ExtensionType::NonTransferable);
+        }
+        if self.close_authority.is_some() {
+            extension_types.push(ExtensionType::MintCloseAuthority);
+        }
+        if self.group_pointer.is_some() {
+            extension_types.push(ExtensionType::GroupPointer);
+        }
+        extension_types
+    }
 }
 
-// State account for AMM pool
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct TransferFeeArgs {
+    pub basis_points: u16,
+    pub maximum_fee: u64,
+}
+
+// Token metadata stored in a mint created by `create_token_with_hook`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct TokenMetadataArgs {
+    pub name: String,
+    pub symbol: String,
+    pub uri: String,
+    pub additional_metadata: Vec<MetadataField>,
+}
+
+// Additional key/value field of a mint's token metadata
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct MetadataField {
+    pub key: String,
+    pub value: String,
+}
+
+// State account for AMM pool. Fields after `bump` were appended in version 1;
+// older pools are upgraded in place by `migrate_pool`.
 #[account]
 pub struct Pool {
     pub authority: Pubkey,
@@ -799,10 +2420,46 @@ pub struct Pool {
     pub token_b_reserve: u64,
     pub lp_token_supply: u64,
     pub bump: u8,
+    pub version: u8,
+    pub high_risk: bool, // A mint has extensions allowed only by the pool's policy
+    pub token_a_program: Pubkey, // SPL Token or Token-2022
+    pub token_b_program: Pubkey,
+    pub lp_token_program: Pubkey,
 }
 
 impl Pool {
-    pub const SPACE: usize = 8 + 32 * 6 + 8 * 4 + 1; // 8 + 192 + 32 + 1 = 233 bytes
+    pub const VERSION: u8 = 1;
+    pub const LEGACY_SPACE: usize = 8 + 32 * 6 + 8 * 4 + 1; // 8 + 192 + 32 + 1 = 233 bytes
+    pub const SPACE: usize = Self::LEGACY_SPACE + 1 + 1 + 32 * 3; // 233 + 1 + 1 + 96 = 331 bytes
+}
+
+// Pool layout before version 1, read by `migrate_pool`
+#[derive(AnchorDeserialize)]
+struct LegacyPool {
+    authority: Pubkey,
+    token_a_mint: Pubkey,
+    token_b_mint: Pubkey,
+    token_a_vault: Pubkey,
+    token_b_vault: Pubkey,
+    lp_token_mint: Pubkey,
+    fee_rate: u64,
+    token_a_reserve: u64,
+    token_b_reserve: u64,
+    lp_token_supply: u64,
+    bump: u8,
+}
+
+// Supply cap of a token created by `create_token_with_hook`
+#[account]
+pub struct SupplyConfig {
+    pub authority: Pubkey,
+    pub mint: Pubkey,
+    pub max_supply: u64,
+    pub bump: u8,
+}
+
+impl SupplyConfig {
+    pub const SPACE: usize = 8 + 32 * 2 + 8 + 1; // 8 + 64 + 8 + 1 = 81 bytes
 }
 
 // Custom errors
@@ -818,6 +2475,81 @@ pub enum AmmError {
     InsufficientOutputAmount,
     #[msg("Transfer hook validation failed")]
     TransferHookValidationFailed,
+    #[msg("Transfer hook accounts are required for hooked mints")]
+    MissingTransferHookAccounts,
+    #[msg("Invalid combination of mint extensions")]
+    InvalidExtensionConfig,
+    #[msg("Mint has no transfer hook extension")]
+    MissingTransferHookExtension,
+    #[msg("Signer is not the mint's transfer hook authority")]
+    InvalidTransferHookAuthority,
+    #[msg("Minting would exceed the supply cap")]
+    SupplyCapExceeded,
+    #[msg("Supply config is missing, unexpected or does not match the mint")]
+    InvalidSupplyConfig,
+    #[msg("Group mint has no token group")]
+    InvalidTokenGroup,
+    #[msg("Token group has reached its max size")]
+    TokenGroupFull,
+    #[msg("Transfer fee calculation failed")]
+    TransferFeeCalculationFailed,
+    #[msg("Mint has an extension pools do not support")]
+    UnsupportedMintExtension,
+    #[msg("Mint has a high-risk extension not allowed by the pool policy")]
+    HighRiskMintExtension,
+    #[msg("Pool has already been migrated")]
+    PoolAlreadyMigrated,
+    #[msg("Signer is not the mint's tax collector")]
+    NotTaxCollector,
 
     //Hello this is new change 
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anchor_spl::token_2022::spl_token_2022::extension::transfer_fee::TransferFee;
+
+    fn fee(epoch: u64, transfer_fee_basis_points: u16, maximum_fee: u64) -> TransferFee {
+        TransferFee {
+            epoch: epoch.into(),
+            maximum_fee: maximum_fee.into(),
+            transfer_fee_basis_points: transfer_fee_basis_points.into(),
+        }
+    }
+
+    fn fee_config(older_transfer_fee: TransferFee, newer_transfer_fee: TransferFee) -> TransferFeeConfig {
+        TransferFeeConfig {
+            transfer_fee_config_authority: OptionalNonZeroPubkey::default(),
+            withdraw_withheld_authority: OptionalNonZeroPubkey::default(),
+            withheld_amount: 0u64.into(),
+            older_transfer_fee,
+            newer_transfer_fee,
+        }
+    }
+
+    #[test]
+    fn mints_without_fee_config_charge_nothing() {
+        assert_eq!(epoch_fee(None, 0, 1_000_000).unwrap(), 0);
+    }
+
+    #[test]
+    fn fee_is_rounded_up_and_capped() {
+        let config = fee_config(fee(0, 100, 5_000), fee(0, 100, 5_000));
+
+        assert_eq!(epoch_fee(Some(&config), 0, 0).unwrap(), 0);
+        assert_eq!(epoch_fee(Some(&config), 0, 1).unwrap(), 1);
+        assert_eq!(epoch_fee(Some(&config), 0, 10_000).unwrap(), 100);
+        assert_eq!(epoch_fee(Some(&config), 0, 10_001).unwrap(), 101);
+        assert_eq!(epoch_fee(Some(&config), 0, 1_000_000).unwrap(), 5_000);
+    }
+
+    #[test]
+    fn newer_fee_applies_from_its_epoch() {
+        let config = fee_config(fee(0, 100, u64::MAX), fee(10, 200, u64::MAX));
+
+        assert_eq!(epoch_fee(Some(&config), 9, 10_000).unwrap(), 100);
+        assert_eq!(epoch_fee(Some(&config), 10, 10_000).unwrap(), 200);
+        assert_eq!(epoch_fee(Some(&config), 11, 10_000).unwrap(), 200);
+    }
+}
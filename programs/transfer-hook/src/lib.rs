@@ -1,11 +1,41 @@
-use anchor_lang::prelude::*;
+use anchor_lang::{
+    prelude::*,
+    solana_program::{
+        program_option::COption,
+        sysvar::instructions::{
+            self as instructions_sysvar, load_current_index_checked, load_instruction_at_checked,
+        },
+    },
+    system_program,
+};
 use anchor_spl::{
-    token_2022::{self, Token2022},
-    token_interface::{Mint, TokenAccount},
+    token_2022::{
+        self,
+        spl_token_2022::{
+            extension::{
+                confidential_transfer::ConfidentialTransferMint,
+                permanent_delegate::PermanentDelegate, transfer_hook::TransferHookAccount,
+                BaseStateWithExtensions, StateWithExtensions,
+            },
+            instruction::AuthorityType,
+            state::{Account as Token2022Account, AccountState},
+        },
+        Token2022,
+    },
+    token_interface::{get_mint_extension_data, Mint, TokenAccount},
+};
+use spl_discriminator::SplDiscriminate;
+use spl_tlv_account_resolution::{
+    account::ExtraAccountMeta, seeds::Seed, state::ExtraAccountMetaList,
 };
+use spl_transfer_hook_interface::instruction::ExecuteInstruction;
 
 declare_id!("E24fiZAUbQNAwEkCcyzm9b4UFi4hhPdqMJgN9ZqntJgq");
 
+/// Version reported by `get_hook_config`. Bump it whenever the hook's rules
+/// or account layouts change, so clients can tell deployments apart.
+pub const HOOK_VERSION: u32 = 2;
+
 #[program]
 pub mod transfer_hook {
     use super::*;
@@ -13,39 +43,1375 @@ pub mod transfer_hook {
     pub fn initialize_extra_account_meta_list(
         ctx: Context<InitializeExtraAccountMetaList>,
     ) -> Result<()> {
-        // Simplified initialization for transfer hook
         msg!("Initializing transfer hook extra account meta list");
         msg!("Extra account meta list: {}", ctx.accounts.extra_account_meta_list.key());
-        
+
+        let account_metas = mint_extra_account_metas(
+            &ctx.accounts.sanctions_config,
+            &ctx.accounts.recovery_config,
+        )?;
+        resize_extra_account_meta_list(
+            &ctx.accounts.extra_account_meta_list,
+            &ctx.accounts.payer,
+            &ctx.accounts.system_program,
+            account_metas.len(),
+        )?;
+        ExtraAccountMetaList::init::<ExecuteInstruction>(
+            &mut ctx.accounts.extra_account_meta_list.try_borrow_mut_data()?,
+            &account_metas,
+        )?;
+
+        Ok(())
+    }
+
+    /// Rewrite the extra account meta list of a mint initialized by an older
+    /// version of this program, growing the account to fit new rule accounts
+    pub fn update_extra_account_meta_list(
+        ctx: Context<UpdateExtraAccountMetaList>,
+    ) -> Result<()> {
+        msg!("Updating extra account meta list for mint: {}", ctx.accounts.mint.key());
+
+        let account_metas = mint_extra_account_metas(
+            &ctx.accounts.sanctions_config,
+            &ctx.accounts.recovery_config,
+        )?;
+        resize_extra_account_meta_list(
+            &ctx.accounts.extra_account_meta_list,
+            &ctx.accounts.payer,
+            &ctx.accounts.system_program,
+            account_metas.len(),
+        )?;
+        ExtraAccountMetaList::update::<ExecuteInstruction>(
+            &mut ctx.accounts.extra_account_meta_list.try_borrow_mut_data()?,
+            &account_metas,
+        )?;
+
         Ok(())
     }
 
+    #[instruction(discriminator = ExecuteInstruction::SPL_DISCRIMINATOR_SLICE)]
     pub fn transfer_hook(ctx: Context<TransferHook>, amount: u64) -> Result<()> {
         msg!("Transfer Hook: Processing transfer of {} tokens", amount);
+
+        // A forced transfer staged by the recovery authority and signed by the
+        // mint's permanent delegate skips every rule, so tokens can leave a
+        // wallet that is sanctioned or no longer whitelisted
+        let recovery_info = &ctx.accounts.recovery_config;
+        if let Some(mut recovery) = load_rule::<RecoveryConfig>(recovery_info)? {
+            let staged = ForcedTransfer {
+                source: ctx.accounts.source_token.key(),
+                destination: ctx.accounts.destination_token.key(),
+                amount,
+            };
+            if recovery.take_staged(&ctx.accounts.owner.key(), &staged) {
+                recovery.try_serialize(&mut &mut recovery_info.try_borrow_mut_data()?[..])?;
+
+                emit!(ForcedTransferEvent {
+                    mint: recovery.mint,
+                    source: staged.source,
+                    destination: staged.destination,
+                    amount,
+                    recovery_authority: recovery.authority,
+                    delegate: recovery.delegate,
+                    timestamp: Clock::get()?.unix_timestamp,
+                });
+                msg!("Forced transfer of {} tokens approved", amount);
+                return Ok(());
+            }
+        }
+
+        if let Some(schedule) = load_rule::<TradingSchedule>(&ctx.accounts.trading_schedule)? {
+            let now = Clock::get()?.unix_timestamp;
+            require!(schedule.is_open(now), TransferHookError::OutsideTradingHours);
+        }
+
+        // Exempt transfer authorities are PDAs registered by a trusted program,
+        // such as an AMM pool sending out of its vault
+        let source_exemption = load_rule::<ExemptAuthority>(&ctx.accounts.source_exemption)?;
+
+        if let Some(limits) = load_rule::<TransferLimits>(&ctx.accounts.transfer_limits)? {
+            let exempt = source_exemption
+                .as_ref()
+                .is_some_and(|exemption| limits.exempt_programs.contains(&exemption.program));
+            require!(
+                exempt || amount >= limits.min_amount,
+                TransferHookError::TransferBelowMinimum
+            );
+        }
+
+        if let Some(config) = load_rule::<SanctionsConfig>(&ctx.accounts.sanctions_config)? {
+            let registry = ctx
+                .remaining_accounts
+                .iter()
+                .find(|account| account.key() == config.registry)
+                .ok_or(TransferHookError::SanctionsRegistryMissing)?;
+            require_keys_eq!(
+                *registry.owner,
+                config.registry_program,
+                TransferHookError::InvalidSanctionsRegistry
+            );
+
+            let registry_data = registry.try_borrow_data()?;
+            for party in [
+                ctx.accounts.source_token.owner,
+                ctx.accounts.destination_token.owner,
+                ctx.accounts.owner.key(),
+            ] {
+                require!(
+                    !config.is_listed(&registry_data, &party)?,
+                    TransferHookError::SanctionedParty
+                );
+            }
+        }
         
         let destination = &ctx.accounts.destination_token;
         
         // Get the destination token account owner
         let destination_owner = destination.owner;
         
-        // Simplified validation - just log the transfer
+        // Exempt destinations are program-controlled, such as AMM pool vaults
+        let destination_exemption =
+            load_rule::<ExemptAuthority>(&ctx.accounts.destination_exemption)?;
+        let destination_exempt = destination_exemption.is_some();
+
+        if let Some(policy) = load_rule::<CallerPolicy>(&ctx.accounts.caller_policy)? {
+            // An approved program is on the call path when one of its
+            // registered PDAs controls either side of the transfer or it issued
+            // the top-level instruction. The owner field of an account proves
+            // nothing, as anyone can create a keypair account owned by a program.
+            let source_approved = source_exemption
+                .as_ref()
+                .is_some_and(|exemption| policy.is_approved(&exemption.program));
+            let destination_approved = destination_exemption
+                .as_ref()
+                .is_some_and(|exemption| policy.is_approved(&exemption.program));
+            let caller_approved = {
+                let instructions = ctx.accounts.instructions_sysvar.to_account_info();
+                let current_index = load_current_index_checked(&instructions)?;
+                let current = load_instruction_at_checked(current_index as usize, &instructions)?;
+                policy.is_approved(&current.program_id)
+            };
+            require!(
+                source_approved || destination_approved || caller_approved,
+                TransferHookError::CallerNotApproved
+            );
+        }
+        
+        // Membership is only enforced for mints that created a whitelist
+        if load_rule::<Whitelist>(&ctx.accounts.whitelist)?.is_some() {
+            let member =
+                load_rule::<WhitelistEntry>(&ctx.accounts.destination_entry)?.is_some();
+            require!(
+                destination_exempt || member,
+                TransferHookError::DestinationNotWhitelisted
+            );
+        }
+
+        if let Some(tax) = load_rule::<SellTaxConfig>(&ctx.accounts.sell_tax_config)? {
+            // Any transfer into a pool vault is taxed, liquidity deposits
+            // included: otherwise depositing and withdrawing the other side
+            // would be an untaxed way to sell
+            let is_sell = destination_exempt;
+            let is_settlement = destination_owner == tax.collector
+                && mint_permanent_delegate(&ctx.accounts.mint.to_account_info())
+                    == Some(ctx.accounts.owner.key());
+
+            if is_sell || is_settlement {
+                // The ledger is only written during a real Token-2022 transfer
+                assert_is_transferring(&ctx.accounts.source_token.to_account_info())?;
+
+                let ledger_info = &ctx.accounts.tax_ledger;
+                let mut ledger = load_rule::<TaxLedger>(ledger_info)?
+                    .ok_or(TransferHookError::TaxLedgerMissing)?;
+                if is_sell {
+                    let tax_owed = (amount as u128 * tax.tax_bps as u128 / 10_000) as u64;
+                    ledger.owed = ledger
+                        .owed
+                        .checked_add(tax_owed)
+                        .ok_or(TransferHookError::TaxOverflow)?;
+                    msg!("Accrued {} sell tax for {}", tax_owed, ledger.wallet);
+                } else {
+                    require!(
+                        amount <= ledger.owed,
+                        TransferHookError::SettlementExceedsOwedTax
+                    );
+                    ledger.owed -= amount;
+                    msg!("Settled {} sell tax for {}", amount, ledger.wallet);
+                }
+                ledger.try_serialize(&mut &mut ledger_info.try_borrow_mut_data()?[..])?;
+            }
+        }
+
         msg!("Transfer approved for destination: {}", destination_owner);
         Ok(())
     }
 
-    pub fn add_to_whitelist(ctx: Context<AddToWhitelist>) -> Result<()> {
+    /// Let the signer, who must be the rule authority, stage forced transfers
+    /// out of lost or sanctioned wallets. The mint must already have a
+    /// permanent delegate, which is the only authority the hook lets bypass
+    /// its rules for a staged transfer.
+    pub fn initialize_recovery(ctx: Context<InitializeRecovery>) -> Result<()> {
+        let delegate = mint_permanent_delegate(&ctx.accounts.mint.to_account_info())
+            .ok_or(TransferHookError::PermanentDelegateMissing)?;
+
+        let recovery = &mut ctx.accounts.recovery_config;
+        recovery.authority = ctx.accounts.authority.key();
+        recovery.mint = ctx.accounts.mint.key();
+        recovery.delegate = delegate;
+        recovery.pending = None;
+        recovery.bump = ctx.bumps.recovery_config;
+
+        // Forced transfers clear the staged transfer from within the hook, so
+        // the recovery config becomes a writable extra account
+        let extra_account_meta_list = &ctx.accounts.extra_account_meta_list;
+        if !extra_account_meta_list.data_is_empty() {
+            let sanctions_config = load_rule::<SanctionsConfig>(&ctx.accounts.sanctions_config)?;
+            let account_metas = mint_extra_account_metas_with(sanctions_config.as_ref(), true)?;
+            resize_extra_account_meta_list(
+                extra_account_meta_list,
+                &ctx.accounts.authority,
+                &ctx.accounts.system_program,
+                account_metas.len(),
+            )?;
+            ExtraAccountMetaList::update::<ExecuteInstruction>(
+                &mut extra_account_meta_list.try_borrow_mut_data()?,
+                &account_metas,
+            )?;
+        }
+
+        msg!("Initialized recovery for mint: {}, delegate: {}", recovery.mint, delegate);
+        Ok(())
+    }
+
+    /// Stage a forced transfer, which the permanent delegate then executes
+    /// with `transfer_checked`. Staging again replaces a pending transfer.
+    pub fn authorize_forced_transfer(
+        ctx: Context<AuthorizeForcedTransfer>,
+        source: Pubkey,
+        destination: Pubkey,
+        amount: u64,
+    ) -> Result<()> {
+        let recovery = &mut ctx.accounts.recovery_config;
+        recovery.pending = Some(ForcedTransfer {
+            source,
+            destination,
+            amount,
+        });
+
+        msg!("Authorized forced transfer of {} tokens from {} to {}", amount, source, destination);
+        Ok(())
+    }
+
+    /// Restrict a mint to transfers made through `approved_programs`, such as
+    /// the AMM or an escrow, instead of wallet-to-wallet
+    pub fn initialize_caller_policy(
+        ctx: Context<InitializeCallerPolicy>,
+        approved_programs: Vec<Pubkey>,
+    ) -> Result<()> {
+        CallerPolicy::validate(&approved_programs)?;
+
+        let policy = &mut ctx.accounts.caller_policy;
+        policy.authority = ctx.accounts.authority.key();
+        policy.mint = ctx.accounts.mint.key();
+        policy.approved_programs = approved_programs;
+        policy.bump = ctx.bumps.caller_policy;
+
+        msg!("Initialized caller policy for mint: {}", policy.mint);
+        Ok(())
+    }
+
+    pub fn update_caller_policy(
+        ctx: Context<UpdateCallerPolicy>,
+        approved_programs: Vec<Pubkey>,
+    ) -> Result<()> {
+        CallerPolicy::validate(&approved_programs)?;
+
+        let policy = &mut ctx.accounts.caller_policy;
+        policy.approved_programs = approved_programs;
+
+        msg!("Updated caller policy for mint: {}", policy.mint);
+        Ok(())
+    }
+
+    /// Tax sells into registered AMM pools at `tax_bps`. Liquidity deposits
+    /// count as sells, since the hook cannot tell them apart from swaps and a
+    /// deposit can be withdrawn as the other token. Tax accrues in each
+    /// seller's `TaxLedger`; the collector settles it by transferring up to the
+    /// owed amount from the seller to itself as the mint's permanent delegate,
+    /// through token2022_amm's `settle_sell_tax` when that delegate is the
+    /// AMM's recovery PDA. Sells fail while the seller has no ledger.
+    pub fn initialize_sell_tax(
+        ctx: Context<InitializeSellTax>,
+        tax_bps: u16,
+        collector: Pubkey,
+    ) -> Result<()> {
+        require!(tax_bps <= 10_000, TransferHookError::InvalidTaxRate);
+        assert_confidential_transfers_refused(
+            &ctx.accounts.mint.to_account_info(),
+            &ctx.accounts.confidential_policy,
+        )?;
+
+        let config = &mut ctx.accounts.sell_tax_config;
+        config.authority = ctx.accounts.authority.key();
+        config.mint = ctx.accounts.mint.key();
+        config.tax_bps = tax_bps;
+        config.collector = collector;
+        config.bump = ctx.bumps.sell_tax_config;
+
+        msg!("Initialized sell tax of {} basis points for mint: {}", tax_bps, config.mint);
+        Ok(())
+    }
+
+    pub fn update_sell_tax(
+        ctx: Context<UpdateSellTax>,
+        tax_bps: u16,
+        collector: Pubkey,
+    ) -> Result<()> {
+        require!(tax_bps <= 10_000, TransferHookError::InvalidTaxRate);
+
+        let config = &mut ctx.accounts.sell_tax_config;
+        config.tax_bps = tax_bps;
+        config.collector = collector;
+
+        msg!("Updated sell tax to {} basis points for mint: {}", tax_bps, config.mint);
+        Ok(())
+    }
+
+    /// Create the tax ledger a wallet needs before it can sell a taxed mint.
+    /// token2022_amm opens it for users depositing or swapping into its pools.
+    pub fn open_tax_ledger(ctx: Context<OpenTaxLedger>) -> Result<()> {
+        let ledger = &mut ctx.accounts.tax_ledger;
+        ledger.mint = ctx.accounts.mint.key();
+        ledger.wallet = ctx.accounts.wallet.key();
+        ledger.owed = 0;
+        ledger.bump = ctx.bumps.tax_ledger;
+
+        msg!("Opened tax ledger for {} on mint: {}", ledger.wallet, ledger.mint);
+        Ok(())
+    }
+
+    /// Record whether a mint may use Token-2022 confidential transfers. Those
+    /// hide amounts from the hook, so they cannot be permitted while the mint
+    /// has amount-based rules (transfer limits or sell tax), and those rules
+    /// cannot be added once they are permitted.
+    pub fn initialize_confidential_transfer_policy(
+        ctx: Context<InitializeConfidentialTransferPolicy>,
+        allow_confidential_transfers: bool,
+    ) -> Result<()> {
+        if allow_confidential_transfers {
+            assert_no_amount_rules(&ctx.accounts.transfer_limits, &ctx.accounts.sell_tax_config)?;
+        }
+
+        let policy = &mut ctx.accounts.confidential_policy;
+        policy.authority = ctx.accounts.authority.key();
+        policy.mint = ctx.accounts.mint.key();
+        policy.allow_confidential_transfers = allow_confidential_transfers;
+        policy.bump = ctx.bumps.confidential_policy;
+
+        msg!(
+            "Confidential transfers {} for mint: {}",
+            if allow_confidential_transfers { "permitted" } else { "refused" },
+            policy.mint
+        );
+        Ok(())
+    }
+
+    pub fn update_confidential_transfer_policy(
+        ctx: Context<UpdateConfidentialTransferPolicy>,
+        allow_confidential_transfers: bool,
+    ) -> Result<()> {
+        if allow_confidential_transfers {
+            assert_no_amount_rules(&ctx.accounts.transfer_limits, &ctx.accounts.sell_tax_config)?;
+        }
+
+        let policy = &mut ctx.accounts.confidential_policy;
+        policy.allow_confidential_transfers = allow_confidential_transfers;
+
+        msg!(
+            "Confidential transfers {} for mint: {}",
+            if allow_confidential_transfers { "permitted" } else { "refused" },
+            policy.mint
+        );
+        Ok(())
+    }
+
+    /// Restrict a mint's transfers to whitelisted destination owners. Only the
+    /// rule authority may create the whitelist; it becomes its authority.
+    pub fn initialize_whitelist(ctx: Context<InitializeWhitelist>) -> Result<()> {
+        let whitelist = &mut ctx.accounts.whitelist;
+        whitelist.authority = ctx.accounts.authority.key();
+        whitelist.accounts = Vec::new();
+
+        let stats = &mut ctx.accounts.whitelist_stats;
+        stats.member_count = 0;
+        stats.bump = ctx.bumps.whitelist_stats;
+
+        msg!(
+            "Initialized whitelist for mint: {} with authority: {}",
+            ctx.accounts.mint.key(),
+            whitelist.authority
+        );
+        Ok(())
+    }
+
+    /// Add a wallet to the whitelist. Its token accounts for the mint passed
+    /// as remaining accounts are thawed; `thaw_token_account` covers the rest.
+    pub fn add_to_whitelist<'info>(
+        ctx: Context<'_, '_, 'info, 'info, AddToWhitelist<'info>>,
+    ) -> Result<()> {
         let new_account = ctx.accounts.new_account.key();
+        create_whitelist_entry(
+            &ctx.accounts.entry,
+            &ctx.accounts.authority,
+            &ctx.accounts.system_program,
+            ctx.accounts.mint.key(),
+            new_account,
+            ctx.bumps.entry,
+        )?;
+        let stats = &mut ctx.accounts.whitelist_stats;
+        stats.member_count = stats
+            .member_count
+            .checked_add(1)
+            .ok_or(TransferHookError::MemberCountOverflow)?;
+        
+        set_token_accounts_frozen(
+            ctx.remaining_accounts,
+            new_account,
+            &ctx.accounts.mint,
+            &ctx.accounts.freeze_authority,
+            ctx.bumps.freeze_authority,
+            &ctx.accounts.token_program,
+            false,
+        )?;
         
         msg!("Added {} to whitelist", new_account);
         Ok(())
     }
 
-    pub fn remove_from_whitelist(ctx: Context<RemoveFromWhitelist>) -> Result<()> {
+    /// Remove a wallet from the whitelist. Its token accounts for the mint
+    /// passed as remaining accounts are frozen unless the wallet is exempt;
+    /// `freeze_token_account` covers the rest.
+    pub fn remove_from_whitelist<'info>(
+        ctx: Context<'_, '_, 'info, 'info, RemoveFromWhitelist<'info>>,
+    ) -> Result<()> {
         let account = ctx.accounts.account_to_remove.key();
+        close_whitelist_entry(&ctx.accounts.entry, &ctx.accounts.authority)?;
+        let stats = &mut ctx.accounts.whitelist_stats;
+        stats.member_count = stats
+            .member_count
+            .checked_sub(1)
+            .ok_or(TransferHookError::MemberCountOverflow)?;
+        
+        if load_rule::<ExemptAuthority>(&ctx.accounts.exemption)?.is_none() {
+            set_token_accounts_frozen(
+                ctx.remaining_accounts,
+                account,
+                &ctx.accounts.mint,
+                &ctx.accounts.freeze_authority,
+                ctx.bumps.freeze_authority,
+                &ctx.accounts.token_program,
+                true,
+            )?;
+        }
         
         msg!("Removed {} from whitelist", account);
         Ok(())
     }
+
+    /// Freeze a token account whose owner is no longer whitelisted. The hook
+    /// PDA `[b"freeze-authority", mint]` must be the mint's freeze authority.
+    pub fn freeze_token_account(ctx: Context<SyncFreezeState>) -> Result<()> {
+        require!(
+            !ctx.accounts.is_member(),
+            TransferHookError::AccountStillWhitelisted
+        );
+
+        let mint = ctx.accounts.mint.key();
+        let signer_seeds: &[&[&[u8]]] = &[&[
+            b"freeze-authority",
+            mint.as_ref(),
+            &[ctx.bumps.freeze_authority],
+        ]];
+        token_2022::freeze_account(CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            token_2022::FreezeAccount {
+                account: ctx.accounts.token_account.to_account_info(),
+                mint: ctx.accounts.mint.to_account_info(),
+                authority: ctx.accounts.freeze_authority.to_account_info(),
+            },
+            signer_seeds,
+        ))?;
+
+        msg!("Froze token account: {}", ctx.accounts.token_account.key());
+        Ok(())
+    }
+
+    /// Thaw a token account whose owner has been re-added to the whitelist
+    pub fn thaw_token_account(ctx: Context<SyncFreezeState>) -> Result<()> {
+        require!(
+            ctx.accounts.is_member(),
+            TransferHookError::AccountNotWhitelisted
+        );
+
+        let mint = ctx.accounts.mint.key();
+        let signer_seeds: &[&[&[u8]]] = &[&[
+            b"freeze-authority",
+            mint.as_ref(),
+            &[ctx.bumps.freeze_authority],
+        ]];
+        token_2022::thaw_account(CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            token_2022::ThawAccount {
+                account: ctx.accounts.token_account.to_account_info(),
+                mint: ctx.accounts.mint.to_account_info(),
+                authority: ctx.accounts.freeze_authority.to_account_info(),
+            },
+            signer_seeds,
+        ))?;
+
+        msg!("Thawed token account: {}", ctx.accounts.token_account.key());
+        Ok(())
+    }
+
+    /// Permanently give up the hook's freeze authority over a mint. Only the
+    /// mint's rule authority may do this.
+    pub fn revoke_freeze_authority(ctx: Context<RevokeFreezeAuthority>) -> Result<()> {
+        let mint = ctx.accounts.mint.key();
+        let signer_seeds: &[&[&[u8]]] = &[&[
+            b"freeze-authority",
+            mint.as_ref(),
+            &[ctx.bumps.freeze_authority],
+        ]];
+        token_2022::set_authority(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                token_2022::SetAuthority {
+                    current_authority: ctx.accounts.freeze_authority.to_account_info(),
+                    account_or_mint: ctx.accounts.mint.to_account_info(),
+                },
+                signer_seeds,
+            ),
+            AuthorityType::FreezeAccount,
+            None,
+        )?;
+
+        msg!("Revoked freeze authority of mint: {}", mint);
+        Ok(())
+    }
+
+    /// Start moving the legacy global whitelist, which stores its members
+    /// inline, to one `WhitelistEntry` account per member of `mint`'s
+    /// whitelist. The mint must not have a whitelist yet: it is created once
+    /// every member has an entry, so enforcement starts with the full list.
+    ///
+    /// Legacy whitelists were created without an authority. The program's
+    /// upgrade authority may claim such a whitelist by passing the program and
+    /// its program data account; it then becomes the whitelist's authority.
+    pub fn start_whitelist_migration(ctx: Context<StartWhitelistMigration>) -> Result<()> {
+        let authority = ctx.accounts.authority.key();
+        let whitelist = &mut ctx.accounts.whitelist;
+        if whitelist.authority != authority {
+            // The program's constraint ties the program data account to it
+            let is_upgrade_authority = match (&ctx.accounts.program, &ctx.accounts.program_data) {
+                (Some(_), Some(program_data)) => {
+                    whitelist.authority == Pubkey::default()
+                        && program_data.upgrade_authority_address == Some(authority)
+                }
+                _ => false,
+            };
+            require!(is_upgrade_authority, TransferHookError::Unauthorized);
+
+            whitelist.authority = authority;
+            msg!("Legacy whitelist claimed by upgrade authority: {}", authority);
+        }
+
+        let migration = &mut ctx.accounts.migration;
+        migration.mint = ctx.accounts.mint.key();
+        migration.total = ctx.accounts.whitelist.accounts.len() as u32;
+        migration.next_index = 0;
+        migration.completed = false;
+        migration.bump = ctx.bumps.migration;
+
+        let stats = &mut ctx.accounts.whitelist_stats;
+        stats.member_count = 0;
+        stats.bump = ctx.bumps.whitelist_stats;
+
+        msg!(
+            "Started whitelist migration of {} accounts to mint: {}",
+            migration.total,
+            migration.mint
+        );
+        Ok(())
+    }
+
+    /// Create the entry accounts for the next chunk of legacy members. The
+    /// entry PDAs are passed as remaining accounts in legacy list order,
+    /// starting at the migration's `next_index`. Once every member has an
+    /// entry, the mint's whitelist is created, the legacy whitelist closed and
+    /// the migration marked complete.
+    pub fn migrate_whitelist_entries<'info>(
+        ctx: Context<'_, '_, 'info, 'info, MigrateWhitelistEntries<'info>>,
+    ) -> Result<()> {
+        let migration = &mut ctx.accounts.migration;
+        require!(!migration.completed, TransferHookError::WhitelistAlreadyMigrated);
+
+        let start = migration.next_index as usize;
+        let legacy_accounts = &ctx.accounts.whitelist.accounts;
+        require!(
+            start + ctx.remaining_accounts.len() <= legacy_accounts.len(),
+            TransferHookError::MigrationChunkOutOfRange
+        );
+
+        let mint = migration.mint;
+        for (wallet, entry) in legacy_accounts[start..]
+            .iter()
+            .zip(ctx.remaining_accounts.iter())
+        {
+            let (expected, bump) = Pubkey::find_program_address(
+                &[b"whitelist-entry", mint.as_ref(), wallet.as_ref()],
+                ctx.program_id,
+            );
+            require_keys_eq!(entry.key(), expected, TransferHookError::InvalidWhitelistEntry);
+
+            // The legacy list may hold a wallet more than once; only its
+            // first occurrence creates an entry and counts as a member
+            if entry.data_is_empty() {
+                create_whitelist_entry(
+                    entry,
+                    &ctx.accounts.authority,
+                    &ctx.accounts.system_program,
+                    mint,
+                    *wallet,
+                    bump,
+                )?;
+                let stats = &mut ctx.accounts.whitelist_stats;
+                stats.member_count = stats
+                    .member_count
+                    .checked_add(1)
+                    .ok_or(TransferHookError::MemberCountOverflow)?;
+            }
+        }
+        migration.next_index += ctx.remaining_accounts.len() as u32;
+
+        if migration.next_index as usize == legacy_accounts.len() {
+            let mint_whitelist = &ctx.accounts.mint_whitelist;
+            create_pda_account(
+                mint_whitelist,
+                &ctx.accounts.authority,
+                &ctx.accounts.system_program,
+                Whitelist::space(0),
+                &[b"whitelist", mint.as_ref(), &[ctx.bumps.mint_whitelist]],
+            )?;
+            Whitelist {
+                authority: ctx.accounts.authority.key(),
+                accounts: Vec::new(),
+            }
+            .try_serialize(&mut &mut mint_whitelist.try_borrow_mut_data()?[..])?;
+
+            // The legacy whitelist is no longer consulted by any mint
+            let legacy_info = ctx.accounts.whitelist.to_account_info();
+            **ctx.accounts.authority.try_borrow_mut_lamports()? += legacy_info.lamports();
+            **legacy_info.try_borrow_mut_lamports()? = 0;
+            legacy_info.assign(&system_program::ID);
+            legacy_info.resize(0)?;
+
+            migration.completed = true;
+            msg!("Whitelist migration completed");
+        }
+
+        msg!(
+            "Migrated whitelist accounts {}/{}",
+            migration.next_index,
+            migration.total
+        );
+        Ok(())
+    }
+
+    /// Let a program exempt authorities it controls (e.g. AMM pool PDAs) from
+    /// the destination whitelist. Exemptions apply to every mint using the
+    /// hook, so only the hook's upgrade authority may trust programs.
+    pub fn register_trusted_program(
+        ctx: Context<RegisterTrustedProgram>,
+        program_id: Pubkey,
+    ) -> Result<()> {
+        let trusted_program = &mut ctx.accounts.trusted_program;
+        trusted_program.program_id = program_id;
+        trusted_program.bump = ctx.bumps.trusted_program;
+
+        msg!("Registered trusted program: {}", program_id);
+        Ok(())
+    }
+
+    /// Called by a trusted program via CPI, signing with one of its own PDAs,
+    /// to exempt that PDA as a transfer destination owner. `seeds`, including
+    /// the bump, must derive the PDA from the trusted program's id.
+    pub fn register_exempt_authority(
+        ctx: Context<RegisterExemptAuthority>,
+        seeds: Vec<Vec<u8>>,
+    ) -> Result<()> {
+        // A signer merely owned by the trusted program could be a keypair
+        // account; only a PDA of the program proves the program signed
+        let seeds: Vec<&[u8]> = seeds.iter().map(Vec::as_slice).collect();
+        let expected = Pubkey::create_program_address(&seeds, &ctx.accounts.trusted_program.program_id)
+            .map_err(|_| TransferHookError::UntrustedProgram)?;
+        require_keys_eq!(
+            ctx.accounts.exempt_authority.key(),
+            expected,
+            TransferHookError::UntrustedProgram
+        );
+
+        let exemption = &mut ctx.accounts.exemption;
+        exemption.authority = ctx.accounts.exempt_authority.key();
+        exemption.program = ctx.accounts.trusted_program.program_id;
+        exemption.bump = ctx.bumps.exemption;
+
+        msg!(
+            "Exempted authority {} owned by trusted program {}",
+            exemption.authority,
+            exemption.program
+        );
+        Ok(())
+    }
+
+    /// Hand whitelist authority over to an M-of-N multisig. From then on
+    /// membership only changes through approved proposals.
+    pub fn create_whitelist_multisig(
+        ctx: Context<CreateWhitelistMultisig>,
+        signers: Vec<Pubkey>,
+        threshold: u8,
+    ) -> Result<()> {
+        WhitelistMultisig::validate(&signers, threshold)?;
+
+        let multisig = &mut ctx.accounts.multisig;
+        multisig.mint = ctx.accounts.mint.key();
+        multisig.signers = signers;
+        multisig.threshold = threshold;
+        multisig.proposal_count = 0;
+        multisig.bump = ctx.bumps.multisig;
+
+        ctx.accounts.whitelist.authority = multisig.key();
+
+        msg!(
+            "Whitelist authority transferred to {}-of-{} multisig: {}",
+            threshold,
+            multisig.signers.len(),
+            multisig.key()
+        );
+        Ok(())
+    }
+
+    /// Propose adding or removing an account; the proposer's approval is
+    /// recorded immediately
+    pub fn propose_whitelist_change(
+        ctx: Context<ProposeWhitelistChange>,
+        action: WhitelistAction,
+        account: Pubkey,
+    ) -> Result<()> {
+        let multisig = &mut ctx.accounts.multisig;
+        let proposer = ctx.accounts.proposer.key();
+        require!(multisig.is_signer(&proposer), TransferHookError::NotMultisigSigner);
+
+        let proposal = &mut ctx.accounts.proposal;
+        proposal.multisig = multisig.key();
+        proposal.index = multisig.proposal_count;
+        proposal.action = action;
+        proposal.account = account;
+        proposal.approvals = vec![proposer];
+        proposal.executed = false;
+        proposal.bump = ctx.bumps.proposal;
+
+        multisig.proposal_count += 1;
+
+        msg!("Whitelist proposal {} created for {}", proposal.index, account);
+        Ok(())
+    }
+
+    pub fn approve_whitelist_proposal(ctx: Context<ApproveWhitelistProposal>) -> Result<()> {
+        let signer = ctx.accounts.signer.key();
+        require!(
+            ctx.accounts.multisig.is_signer(&signer),
+            TransferHookError::NotMultisigSigner
+        );
+
+        let proposal = &mut ctx.accounts.proposal;
+        require!(!proposal.executed, TransferHookError::ProposalAlreadyExecuted);
+        require!(
+            !proposal.approvals.contains(&signer),
+            TransferHookError::ProposalAlreadyApproved
+        );
+        proposal.approvals.push(signer);
+
+        msg!(
+            "Whitelist proposal {} approved by {} ({} approvals)",
+            proposal.index,
+            signer,
+            proposal.approvals.len()
+        );
+        Ok(())
+    }
+
+    /// Apply a proposal once it has reached the multisig threshold. Token
+    /// accounts of the proposal's wallet passed as remaining accounts are
+    /// thawed or frozen as in `add_to_whitelist` and `remove_from_whitelist`.
+    pub fn execute_whitelist_proposal<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ExecuteWhitelistProposal<'info>>,
+    ) -> Result<()> {
+        let proposal = &mut ctx.accounts.proposal;
+        require!(!proposal.executed, TransferHookError::ProposalAlreadyExecuted);
+        require!(
+            proposal.approvals.len() >= ctx.accounts.multisig.threshold as usize,
+            TransferHookError::ThresholdNotMet
+        );
+
+        let stats = &mut ctx.accounts.whitelist_stats;
+        let freeze = match proposal.action {
+            WhitelistAction::Add => {
+                create_whitelist_entry(
+                    &ctx.accounts.entry,
+                    &ctx.accounts.payer,
+                    &ctx.accounts.system_program,
+                    ctx.accounts.multisig.mint,
+                    proposal.account,
+                    ctx.bumps.entry,
+                )?;
+                stats.member_count = stats
+                    .member_count
+                    .checked_add(1)
+                    .ok_or(TransferHookError::MemberCountOverflow)?;
+                Some(false)
+            }
+            WhitelistAction::Remove => {
+                close_whitelist_entry(&ctx.accounts.entry, &ctx.accounts.payer)?;
+                stats.member_count = stats
+                    .member_count
+                    .checked_sub(1)
+                    .ok_or(TransferHookError::MemberCountOverflow)?;
+                load_rule::<ExemptAuthority>(&ctx.accounts.exemption)?
+                    .is_none()
+                    .then_some(true)
+            }
+        };
+        if let Some(freeze) = freeze {
+            set_token_accounts_frozen(
+                ctx.remaining_accounts,
+                proposal.account,
+                &ctx.accounts.mint,
+                &ctx.accounts.freeze_authority,
+                ctx.bumps.freeze_authority,
+                &ctx.accounts.token_program,
+                freeze,
+            )?;
+        }
+        proposal.executed = true;
+
+        msg!("Executed whitelist proposal {} for {}", proposal.index, proposal.account);
+        Ok(())
+    }
+
+    /// Create the trading-hours schedule for a mint. Only the rule authority
+    /// may attach a schedule; it becomes the schedule's authority.
+    pub fn initialize_trading_schedule(
+        ctx: Context<InitializeTradingSchedule>,
+        weekly_windows: [TradingWindow; 7],
+        holidays: Vec<u32>,
+    ) -> Result<()> {
+        TradingSchedule::validate(&weekly_windows, &holidays)?;
+
+        let schedule = &mut ctx.accounts.trading_schedule;
+        schedule.authority = ctx.accounts.authority.key();
+        schedule.mint = ctx.accounts.mint.key();
+        schedule.weekly_windows = weekly_windows;
+        schedule.holidays = holidays;
+        schedule.bump = ctx.bumps.trading_schedule;
+
+        msg!("Initialized trading schedule for mint: {}", schedule.mint);
+        Ok(())
+    }
+
+    /// Replace the weekly windows and holiday list of an existing schedule
+    pub fn update_trading_schedule(
+        ctx: Context<UpdateTradingSchedule>,
+        weekly_windows: [TradingWindow; 7],
+        holidays: Vec<u32>,
+    ) -> Result<()> {
+        TradingSchedule::validate(&weekly_windows, &holidays)?;
+
+        let schedule = &mut ctx.accounts.trading_schedule;
+        schedule.weekly_windows = weekly_windows;
+        schedule.holidays = holidays;
+
+        msg!("Updated trading schedule for mint: {}", schedule.mint);
+        Ok(())
+    }
+
+    /// Create the minimum transfer amount rule for a mint. Transfers whose
+    /// authority is an exempt authority registered by one of `exempt_programs`
+    /// bypass the minimum.
+    pub fn initialize_transfer_limits(
+        ctx: Context<InitializeTransferLimits>,
+        min_amount: u64,
+        exempt_programs: Vec<Pubkey>,
+    ) -> Result<()> {
+        TransferLimits::validate(&exempt_programs)?;
+        assert_confidential_transfers_refused(
+            &ctx.accounts.mint.to_account_info(),
+            &ctx.accounts.confidential_policy,
+        )?;
+
+        let limits = &mut ctx.accounts.transfer_limits;
+        limits.authority = ctx.accounts.authority.key();
+        limits.mint = ctx.accounts.mint.key();
+        limits.min_amount = min_amount;
+        limits.exempt_programs = exempt_programs;
+        limits.bump = ctx.bumps.transfer_limits;
+
+        msg!("Initialized transfer limits for mint: {}, minimum: {}", limits.mint, min_amount);
+        Ok(())
+    }
+
+    /// Replace the minimum amount and exempt programs of an existing rule
+    pub fn update_transfer_limits(
+        ctx: Context<UpdateTransferLimits>,
+        min_amount: u64,
+        exempt_programs: Vec<Pubkey>,
+    ) -> Result<()> {
+        TransferLimits::validate(&exempt_programs)?;
+
+        let limits = &mut ctx.accounts.transfer_limits;
+        limits.min_amount = min_amount;
+        limits.exempt_programs = exempt_programs;
+
+        msg!("Updated transfer limits for mint: {}, minimum: {}", limits.mint, min_amount);
+        Ok(())
+    }
+
+    /// Designate the sanctions registry consulted for a mint's transfers and
+    /// add it to the mint's extra account meta list
+    pub fn initialize_sanctions_config(
+        ctx: Context<InitializeSanctionsConfig>,
+        registry: Pubkey,
+        registry_program: Pubkey,
+        data_offset: u32,
+    ) -> Result<()> {
+        let config = &mut ctx.accounts.sanctions_config;
+        config.authority = ctx.accounts.authority.key();
+        config.mint = ctx.accounts.mint.key();
+        config.registry = registry;
+        config.registry_program = registry_program;
+        config.data_offset = data_offset;
+        config.bump = ctx.bumps.sanctions_config;
+
+        let recovery_configured =
+            load_rule::<RecoveryConfig>(&ctx.accounts.recovery_config)?.is_some();
+        let account_metas = mint_extra_account_metas_with(Some(config), recovery_configured)?;
+        resize_extra_account_meta_list(
+            &ctx.accounts.extra_account_meta_list,
+            &ctx.accounts.authority,
+            &ctx.accounts.system_program,
+            account_metas.len(),
+        )?;
+        ExtraAccountMetaList::update::<ExecuteInstruction>(
+            &mut ctx.accounts.extra_account_meta_list.try_borrow_mut_data()?,
+            &account_metas,
+        )?;
+
+        msg!("Sanctions registry {} configured for mint: {}", registry, config.mint);
+        Ok(())
+    }
+
+    /// Point a mint at a different sanctions registry
+    pub fn update_sanctions_config(
+        ctx: Context<UpdateSanctionsConfig>,
+        registry: Pubkey,
+        registry_program: Pubkey,
+        data_offset: u32,
+    ) -> Result<()> {
+        let config = &mut ctx.accounts.sanctions_config;
+        config.registry = registry;
+        config.registry_program = registry_program;
+        config.data_offset = data_offset;
+
+        let recovery_configured =
+            load_rule::<RecoveryConfig>(&ctx.accounts.recovery_config)?.is_some();
+        let account_metas = mint_extra_account_metas_with(Some(config), recovery_configured)?;
+        resize_extra_account_meta_list(
+            &ctx.accounts.extra_account_meta_list,
+            &ctx.accounts.authority,
+            &ctx.accounts.system_program,
+            account_metas.len(),
+        )?;
+        ExtraAccountMetaList::update::<ExecuteInstruction>(
+            &mut ctx.accounts.extra_account_meta_list.try_borrow_mut_data()?,
+            &account_metas,
+        )?;
+
+        msg!("Sanctions registry {} configured for mint: {}", registry, config.mint);
+        Ok(())
+    }
+
+    /// Let `authority` configure the mint's hook rules in place of the mint
+    /// authority, which may later move to a program PDA or be revoked
+    pub fn initialize_rule_authority(
+        ctx: Context<InitializeRuleAuthority>,
+        authority: Pubkey,
+    ) -> Result<()> {
+        let rule_authority = &mut ctx.accounts.rule_authority;
+        rule_authority.mint = ctx.accounts.mint.key();
+        rule_authority.authority = authority;
+        rule_authority.bump = ctx.bumps.rule_authority;
+
+        msg!(
+            "Rule authority of mint {} set to: {}",
+            rule_authority.mint,
+            authority
+        );
+        Ok(())
+    }
+
+    pub fn set_rule_authority(
+        ctx: Context<SetRuleAuthority>,
+        new_authority: Pubkey,
+    ) -> Result<()> {
+        let rule_authority = &mut ctx.accounts.rule_authority;
+        rule_authority.authority = new_authority;
+
+        msg!(
+            "Rule authority of mint {} set to: {}",
+            rule_authority.mint,
+            new_authority
+        );
+        Ok(())
+    }
+
+    /// Summarise the hook configuration of a mint for integrators. The
+    /// `HookConfig` is returned through `set_return_data`, so it can be read
+    /// by simulating the instruction or by a calling program.
+    pub fn get_hook_config(ctx: Context<GetHookConfig>) -> Result<HookConfig> {
+        let schedule = load_rule::<TradingSchedule>(&ctx.accounts.trading_schedule)?;
+        let limits = load_rule::<TransferLimits>(&ctx.accounts.transfer_limits)?;
+        let sanctions = load_rule::<SanctionsConfig>(&ctx.accounts.sanctions_config)?;
+        let caller_policy = load_rule::<CallerPolicy>(&ctx.accounts.caller_policy)?;
+        let sell_tax = load_rule::<SellTaxConfig>(&ctx.accounts.sell_tax_config)?;
+        let whitelist = load_rule::<Whitelist>(&ctx.accounts.whitelist)?;
+        let stats = load_rule::<WhitelistStats>(&ctx.accounts.whitelist_stats)?;
+        let confidential_policy =
+            load_rule::<ConfidentialTransferPolicy>(&ctx.accounts.confidential_policy)?;
+        let recovery = load_rule::<RecoveryConfig>(&ctx.accounts.recovery_config)?;
+
+        let paused = match &schedule {
+            Some(schedule) => !schedule.is_open(Clock::get()?.unix_timestamp),
+            None => false,
+        };
+        // Members of a per-mint whitelist only exist as entries
+        let whitelist_size = match (&whitelist, stats) {
+            (Some(_), Some(stats)) => stats.member_count,
+            _ => 0,
+        };
+
+        Ok(HookConfig {
+            version: HOOK_VERSION,
+            paused,
+            whitelist_size,
+            whitelist_authority: whitelist.map(|whitelist| whitelist.authority),
+            trading_schedule_authority: schedule.map(|schedule| schedule.authority),
+            transfer_limits_authority: limits.map(|limits| limits.authority),
+            sanctions_authority: sanctions.map(|config| config.authority),
+            caller_policy_authority: caller_policy.map(|policy| policy.authority),
+            sell_tax_authority: sell_tax.map(|tax| tax.authority),
+            confidential_transfers_allowed: confidential_policy
+                .is_some_and(|policy| policy.allow_confidential_transfers),
+            recovery_authority: recovery.map(|recovery| recovery.authority),
+            freeze_authority: ctx.accounts.mint.freeze_authority.into(),
+        })
+    }
+}
+
+/// Extra accounts for a mint: the rule accounts every mint carries, followed
+/// by the mint's sanctions registry when one is configured
+fn mint_extra_account_metas(
+    sanctions_config: &AccountInfo,
+    recovery_config: &AccountInfo,
+) -> Result<Vec<ExtraAccountMeta>> {
+    let config = load_rule::<SanctionsConfig>(sanctions_config)?;
+    let recovery_configured = load_rule::<RecoveryConfig>(recovery_config)?.is_some();
+    mint_extra_account_metas_with(config.as_ref(), recovery_configured)
+}
+
+fn mint_extra_account_metas_with(
+    sanctions_config: Option<&SanctionsConfig>,
+    recovery_configured: bool,
+) -> Result<Vec<ExtraAccountMeta>> {
+    let mut account_metas =
+        InitializeExtraAccountMetaList::extra_account_metas(recovery_configured)?;
+    if let Some(config) = sanctions_config {
+        account_metas.push(ExtraAccountMeta::new_with_pubkey(
+            &config.registry,
+            false,
+            false,
+        )?);
+    }
+    Ok(account_metas)
+}
+
+/// Resize the extra account meta list to hold `num_metas` entries, topping up
+/// rent from `payer` when the account grows
+fn resize_extra_account_meta_list<'info>(
+    extra_account_meta_list: &AccountInfo<'info>,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    num_metas: usize,
+) -> Result<()> {
+    let new_len = ExtraAccountMetaList::size_of(num_metas)?;
+    let rent_due = Rent::get()?
+        .minimum_balance(new_len)
+        .saturating_sub(extra_account_meta_list.lamports());
+    if rent_due > 0 {
+        system_program::transfer(
+            CpiContext::new(
+                system_program.clone(),
+                system_program::Transfer {
+                    from: payer.clone(),
+                    to: extra_account_meta_list.clone(),
+                },
+            ),
+            rent_due,
+        )?;
+    }
+    extra_account_meta_list.resize(new_len)?;
+    Ok(())
+}
+
+/// Create the membership entry for `wallet` in `mint`'s whitelist at its PDA
+fn create_whitelist_entry<'info>(
+    entry: &AccountInfo<'info>,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    mint: Pubkey,
+    wallet: Pubkey,
+    bump: u8,
+) -> Result<()> {
+    require!(entry.data_is_empty(), TransferHookError::AccountAlreadyWhitelisted);
+
+    create_pda_account(
+        entry,
+        payer,
+        system_program,
+        WhitelistEntry::SPACE,
+        &[b"whitelist-entry", mint.as_ref(), wallet.as_ref(), &[bump]],
+    )?;
+
+    WhitelistEntry { mint, wallet, bump }
+        .try_serialize(&mut &mut entry.try_borrow_mut_data()?[..])?;
+    Ok(())
+}
+
+/// Create a PDA owned by this program. A bare `create_account` fails once
+/// anyone has sent lamports to the address, so a funded address is instead
+/// topped up to rent exemption, allocated and assigned.
+fn create_pda_account<'info>(
+    account: &AccountInfo<'info>,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    space: usize,
+    signer_seeds: &[&[u8]],
+) -> Result<()> {
+    let rent = Rent::get()?.minimum_balance(space);
+    let current_lamports = account.lamports();
+
+    if current_lamports == 0 {
+        return system_program::create_account(
+            CpiContext::new_with_signer(
+                system_program.clone(),
+                system_program::CreateAccount {
+                    from: payer.clone(),
+                    to: account.clone(),
+                },
+                &[signer_seeds],
+            ),
+            rent,
+            space as u64,
+            &crate::ID,
+        );
+    }
+
+    let top_up = rent.saturating_sub(current_lamports);
+    if top_up > 0 {
+        system_program::transfer(
+            CpiContext::new(
+                system_program.clone(),
+                system_program::Transfer {
+                    from: payer.clone(),
+                    to: account.clone(),
+                },
+            ),
+            top_up,
+        )?;
+    }
+    system_program::allocate(
+        CpiContext::new_with_signer(
+            system_program.clone(),
+            system_program::Allocate {
+                account_to_allocate: account.clone(),
+            },
+            &[signer_seeds],
+        ),
+        space as u64,
+    )?;
+    system_program::assign(
+        CpiContext::new_with_signer(
+            system_program.clone(),
+            system_program::Assign {
+                account_to_assign: account.clone(),
+            },
+            &[signer_seeds],
+        ),
+        &crate::ID,
+    )
+}
+
+/// Freeze or thaw `wallet`'s token accounts for `mint`, skipping accounts
+/// already in that state
+fn set_token_accounts_frozen<'info>(
+    token_accounts: &'info [AccountInfo<'info>],
+    wallet: Pubkey,
+    mint: &InterfaceAccount<'info, Mint>,
+    freeze_authority: &AccountInfo<'info>,
+    freeze_authority_bump: u8,
+    token_program: &AccountInfo<'info>,
+    freeze: bool,
+) -> Result<()> {
+    let mint_key = mint.key();
+    let signer_seeds: &[&[&[u8]]] = &[&[
+        b"freeze-authority",
+        mint_key.as_ref(),
+        &[freeze_authority_bump],
+    ]];
+
+    for token_account in token_accounts {
+        require_keys_eq!(
+            *token_account.owner,
+            token_2022::ID,
+            ErrorCode::AccountOwnedByWrongProgram
+        );
+        let frozen = {
+            let data = token_account.try_borrow_data()?;
+            let account = StateWithExtensions::<Token2022Account>::unpack(&data)?;
+            require_keys_eq!(account.base.mint, mint_key, ErrorCode::ConstraintTokenMint);
+            require_keys_eq!(account.base.owner, wallet, ErrorCode::ConstraintTokenOwner);
+            account.base.state == AccountState::Frozen
+        };
+        if frozen == freeze {
+            continue;
+        }
+
+        if freeze {
+            token_2022::freeze_account(CpiContext::new_with_signer(
+                token_program.clone(),
+                token_2022::FreezeAccount {
+                    account: token_account.clone(),
+                    mint: mint.to_account_info(),
+                    authority: freeze_authority.clone(),
+                },
+                signer_seeds,
+            ))?;
+            msg!("Froze token account: {}", token_account.key());
+        } else {
+            token_2022::thaw_account(CpiContext::new_with_signer(
+                token_program.clone(),
+                token_2022::ThawAccount {
+                    account: token_account.clone(),
+                    mint: mint.to_account_info(),
+                    authority: freeze_authority.clone(),
+                },
+                signer_seeds,
+            ))?;
+            msg!("Thawed token account: {}", token_account.key());
+        }
+    }
+    Ok(())
+}
+
+/// Close a membership entry, returning its rent to `receiver`
+fn close_whitelist_entry<'info>(
+    entry: &AccountInfo<'info>,
+    receiver: &AccountInfo<'info>,
+) -> Result<()> {
+    require!(
+        load_rule::<WhitelistEntry>(entry)?.is_some(),
+        TransferHookError::AccountNotWhitelisted
+    );
+
+    **receiver.try_borrow_mut_lamports()? += entry.lamports();
+    **entry.try_borrow_mut_lamports()? = 0;
+    entry.assign(&system_program::ID);
+    entry.resize(0)?;
+    Ok(())
+}
+
+/// Refuse to permit confidential transfers while amount-based rules exist
+fn assert_no_amount_rules(transfer_limits: &AccountInfo, sell_tax_config: &AccountInfo) -> Result<()> {
+    require!(
+        load_rule::<TransferLimits>(transfer_limits)?.is_none()
+            && load_rule::<SellTaxConfig>(sell_tax_config)?.is_none(),
+        TransferHookError::ConfidentialTransfersWithAmountRules
+    );
+    Ok(())
+}
+
+/// Refuse to add an amount-based rule to a mint permitting confidential
+/// transfers, whether through its policy or by carrying the Token-2022
+/// extension, which a policy refusing them cannot switch off
+fn assert_confidential_transfers_refused(
+    mint: &AccountInfo,
+    confidential_policy: &AccountInfo,
+) -> Result<()> {
+    let permitted_by_policy = load_rule::<ConfidentialTransferPolicy>(confidential_policy)?
+        .is_some_and(|policy| policy.allow_confidential_transfers);
+    let permitted_by_mint = get_mint_extension_data::<ConfidentialTransferMint>(mint).is_ok();
+    require!(
+        !permitted_by_policy && !permitted_by_mint,
+        TransferHookError::ConfidentialTransfersWithAmountRules
+    );
+    Ok(())
+}
+
+/// Whether `signer` may configure the mint's hook rules: the registered rule
+/// authority if there is one, otherwise the mint authority
+fn is_rule_authority(mint: &Mint, rule_authority: &AccountInfo, signer: Pubkey) -> Result<bool> {
+    Ok(match load_rule::<RuleAuthority>(rule_authority)? {
+        Some(rule_authority) => rule_authority.authority == signer,
+        None => mint.mint_authority == COption::Some(signer),
+    })
+}
+
+/// Reject calls made outside a Token-2022 transfer, which sets the
+/// `transferring` flag on the source account while invoking the hook
+fn assert_is_transferring(source_token: &AccountInfo) -> Result<()> {
+    let data = source_token.try_borrow_data()?;
+    let account = StateWithExtensions::<Token2022Account>::unpack(&data)?;
+    let extension = account.get_extension::<TransferHookAccount>()?;
+    require!(
+        bool::from(extension.transferring),
+        TransferHookError::NotTransferring
+    );
+    Ok(())
+}
+
+fn mint_permanent_delegate(mint: &AccountInfo) -> Option<Pubkey> {
+    get_mint_extension_data::<PermanentDelegate>(mint)
+        .ok()
+        .and_then(|extension| extension.delegate.into())
+}
+
+/// Load an optional per-mint rule account passed through the extra account
+/// meta list. A rule account that was never created leaves the rule disabled.
+fn load_rule<T: AccountDeserialize + Owner>(info: &AccountInfo) -> Result<Option<T>> {
+    if info.data_is_empty() {
+        return Ok(None);
+    }
+    require_keys_eq!(*info.owner, T::owner(), ErrorCode::AccountOwnedByWrongProgram);
+    let data = info.try_borrow_data()?;
+    Ok(Some(T::try_deserialize(&mut &data[..])?))
 }
 
 #[derive(Accounts)]
@@ -57,7 +1423,9 @@ pub struct InitializeExtraAccountMetaList<'info> {
     #[account(
         init,
         payer = payer,
-        space = 1000, // Simplified space allocation
+        space = ExtraAccountMetaList::size_of(
+            InitializeExtraAccountMetaList::extra_account_metas(false)?.len()
+        )?,
         seeds = [b"extra-account-metas", mint.key().as_ref()],
         bump
     )]
@@ -65,77 +1433,1670 @@ pub struct InitializeExtraAccountMetaList<'info> {
 
     pub mint: InterfaceAccount<'info, Mint>,
     pub system_program: Program<'info, System>,
-}
 
-#[derive(Accounts)]
-pub struct TransferHook<'info> {
+    /// CHECK: Sanctions config for the mint, empty when no registry is designated
     #[account(
-        token::mint = mint,
-        token::authority = owner,
+        seeds = [b"sanctions-config", mint.key().as_ref()],
+        bump
     )]
-    pub source_token: InterfaceAccount<'info, TokenAccount>,
-    
-    pub mint: InterfaceAccount<'info, Mint>,
-    
+    pub sanctions_config: UncheckedAccount<'info>,
+
+    /// CHECK: Recovery config for the mint, empty when forced transfers are disabled
     #[account(
-        token::mint = mint,
+        seeds = [b"recovery", mint.key().as_ref()],
+        bump
     )]
-    pub destination_token: InterfaceAccount<'info, TokenAccount>,
-    
-    /// CHECK: source token account owner, can be SystemAccount or PDA
+    pub recovery_config: UncheckedAccount<'info>,
+}
+
+impl<'info> InitializeExtraAccountMetaList<'info> {
+    /// Accounts appended by Token-2022 after the standard `Execute` accounts
+    /// (source, mint, destination, owner, extra account meta list), in the
+    /// order `TransferHook` expects them. A configured sanctions registry is
+    /// appended after these and reaches the hook as a remaining account.
+    ///
+    /// The recovery config is only writable once recovery is configured, so
+    /// transfers of other mints do not write-lock it.
+    pub fn extra_account_metas(recovery_configured: bool) -> Result<Vec<ExtraAccountMeta>> {
+        Ok(vec![
+            // index 5: trading schedule for the mint
+            ExtraAccountMeta::new_with_seeds(
+                &[
+                    Seed::Literal {
+                        bytes: b"trading-schedule".to_vec(),
+                    },
+                    Seed::AccountKey { index: 1 },
+                ],
+                false,
+                false,
+            )?,
+            // index 6: transfer limits for the mint
+            ExtraAccountMeta::new_with_seeds(
+                &[
+                    Seed::Literal {
+                        bytes: b"transfer-limits".to_vec(),
+                    },
+                    Seed::AccountKey { index: 1 },
+                ],
+                false,
+                false,
+            )?,
+            // index 7: sanctions config for the mint
+            ExtraAccountMeta::new_with_seeds(
+                &[
+                    Seed::Literal {
+                        bytes: b"sanctions-config".to_vec(),
+                    },
+                    Seed::AccountKey { index: 1 },
+                ],
+                false,
+                false,
+            )?,
+            // index 8: destination whitelist for the mint
+            ExtraAccountMeta::new_with_seeds(
+                &[
+                    Seed::Literal {
+                        bytes: b"whitelist".to_vec(),
+                    },
+                    Seed::AccountKey { index: 1 },
+                ],
+                false,
+                false,
+            )?,
+            // index 9: exemption for the destination token account's owner
+            ExtraAccountMeta::new_with_seeds(
+                &[
+                    Seed::Literal {
+                        bytes: b"exempt-authority".to_vec(),
+                    },
+                    Seed::AccountData {
+                        account_index: 2,
+                        data_index: 32,
+                        length: 32,
+                    },
+                ],
+                false,
+                false,
+            )?,
+            // index 10: whitelist entry for the destination token account's owner
+            ExtraAccountMeta::new_with_seeds(
+                &[
+                    Seed::Literal {
+                        bytes: b"whitelist-entry".to_vec(),
+                    },
+                    Seed::AccountKey { index: 1 },
+                    Seed::AccountData {
+                        account_index: 2,
+                        data_index: 32,
+                        length: 32,
+                    },
+                ],
+                false,
+                false,
+            )?,
+            // index 11: sell tax config for the mint
+            ExtraAccountMeta::new_with_seeds(
+                &[
+                    Seed::Literal {
+                        bytes: b"sell-tax".to_vec(),
+                    },
+                    Seed::AccountKey { index: 1 },
+                ],
+                false,
+                false,
+            )?,
+            // index 12: tax ledger of the source token account's owner
+            ExtraAccountMeta::new_with_seeds(
+                &[
+                    Seed::Literal {
+                        bytes: b"tax-ledger".to_vec(),
+                    },
+                    Seed::AccountKey { index: 1 },
+                    Seed::AccountData {
+                        account_index: 0,
+                        data_index: 32,
+                        length: 32,
+                    },
+                ],
+                false,
+                true,
+            )?,
+            // index 13: caller policy for the mint
+            ExtraAccountMeta::new_with_seeds(
+                &[
+                    Seed::Literal {
+                        bytes: b"caller-policy".to_vec(),
+                    },
+                    Seed::AccountKey { index: 1 },
+                ],
+                false,
+                false,
+            )?,
+            // index 14: instructions sysvar
+            ExtraAccountMeta::new_with_pubkey(&instructions_sysvar::ID, false, false)?,
+            // index 15: recovery config for the mint
+            ExtraAccountMeta::new_with_seeds(
+                &[
+                    Seed::Literal {
+                        bytes: b"recovery".to_vec(),
+                    },
+                    Seed::AccountKey { index: 1 },
+                ],
+                false,
+                recovery_configured,
+            )?,
+            // index 16: exemption for the transfer authority
+            ExtraAccountMeta::new_with_seeds(
+                &[
+                    Seed::Literal {
+                        bytes: b"exempt-authority".to_vec(),
+                    },
+                    Seed::AccountKey { index: 3 },
+                ],
+                false,
+                false,
+            )?,
+        ])
+    }
+}
+
+#[derive(Accounts)]
+pub struct UpdateExtraAccountMetaList<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// CHECK: Extra account meta list account for transfer hook
+    #[account(
+        mut,
+        seeds = [b"extra-account-metas", mint.key().as_ref()],
+        bump
+    )]
+    pub extra_account_meta_list: AccountInfo<'info>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+    pub system_program: Program<'info, System>,
+
+    /// CHECK: Sanctions config for the mint, empty when no registry is designated
+    #[account(
+        seeds = [b"sanctions-config", mint.key().as_ref()],
+        bump
+    )]
+    pub sanctions_config: UncheckedAccount<'info>,
+
+    /// CHECK: Recovery config for the mint, empty when forced transfers are disabled
+    #[account(
+        seeds = [b"recovery", mint.key().as_ref()],
+        bump
+    )]
+    pub recovery_config: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct TransferHook<'info> {
+    // The authority may be a delegate of the source account; Token-2022 has
+    // already checked it before invoking the hook
+    #[account(
+        token::mint = mint,
+    )]
+    pub source_token: InterfaceAccount<'info, TokenAccount>,
+    
+    pub mint: InterfaceAccount<'info, Mint>,
+    
+    #[account(
+        token::mint = mint,
+    )]
+    pub destination_token: InterfaceAccount<'info, TokenAccount>,
+    
+    /// CHECK: transfer authority, the source owner or a delegate, can be SystemAccount or PDA
     pub owner: UncheckedAccount<'info>,
     
     /// CHECK: ExtraAccountMetaList Account
     #[account(
-        seeds = [b"extra-account-metas", mint.key().as_ref()],
+        seeds = [b"extra-account-metas", mint.key().as_ref()],
+        bump
+    )]
+    pub extra_account_meta_list: UncheckedAccount<'info>,
+
+    /// CHECK: Trading schedule for the mint, empty when the mint trades around the clock
+    #[account(
+        seeds = [b"trading-schedule", mint.key().as_ref()],
+        bump
+    )]
+    pub trading_schedule: UncheckedAccount<'info>,
+
+    /// CHECK: Transfer limits for the mint, empty when no minimum is enforced
+    #[account(
+        seeds = [b"transfer-limits", mint.key().as_ref()],
+        bump
+    )]
+    pub transfer_limits: UncheckedAccount<'info>,
+
+    /// CHECK: Sanctions config for the mint, empty when no registry is designated
+    #[account(
+        seeds = [b"sanctions-config", mint.key().as_ref()],
+        bump
+    )]
+    pub sanctions_config: UncheckedAccount<'info>,
+
+    /// CHECK: Destination whitelist for the mint, empty when membership is not enforced
+    #[account(
+        seeds = [b"whitelist", mint.key().as_ref()],
+        bump
+    )]
+    pub whitelist: UncheckedAccount<'info>,
+
+    /// CHECK: Exemption for the destination owner, empty unless registered by a trusted program
+    #[account(
+        seeds = [b"exempt-authority", destination_token.owner.as_ref()],
+        bump
+    )]
+    pub destination_exemption: UncheckedAccount<'info>,
+
+    /// CHECK: Whitelist entry for the destination owner, empty when not a member
+    #[account(
+        seeds = [b"whitelist-entry", mint.key().as_ref(), destination_token.owner.as_ref()],
+        bump
+    )]
+    pub destination_entry: UncheckedAccount<'info>,
+
+    /// CHECK: Sell tax config for the mint, empty when sells are untaxed
+    #[account(
+        seeds = [b"sell-tax", mint.key().as_ref()],
+        bump
+    )]
+    pub sell_tax_config: UncheckedAccount<'info>,
+
+    /// CHECK: Tax ledger of the source owner, empty until opened
+    #[account(
+        mut,
+        seeds = [b"tax-ledger", mint.key().as_ref(), source_token.owner.as_ref()],
+        bump
+    )]
+    pub tax_ledger: UncheckedAccount<'info>,
+
+    /// CHECK: Caller policy for the mint, empty when wallet-to-wallet transfers are allowed
+    #[account(
+        seeds = [b"caller-policy", mint.key().as_ref()],
+        bump
+    )]
+    pub caller_policy: UncheckedAccount<'info>,
+
+    /// CHECK: Instructions sysvar
+    #[account(address = instructions_sysvar::ID)]
+    pub instructions_sysvar: UncheckedAccount<'info>,
+
+    /// CHECK: Recovery config for the mint, empty when forced transfers are
+    /// disabled and only writable when they are enabled
+    #[account(
+        seeds = [b"recovery", mint.key().as_ref()],
+        bump
+    )]
+    pub recovery_config: UncheckedAccount<'info>,
+
+    /// CHECK: Exemption for the transfer authority, empty unless registered by a trusted program
+    #[account(
+        seeds = [b"exempt-authority", owner.key().as_ref()],
+        bump
+    )]
+    pub source_exemption: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeRecovery<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    /// CHECK: Rule authority of the mint, empty while the mint authority configures rules
+    #[account(
+        seeds = [b"rule-authority", mint.key().as_ref()],
+        bump,
+        constraint = is_rule_authority(&mint, &rule_authority, authority.key())?
+            @ TransferHookError::Unauthorized,
+    )]
+    pub rule_authority: UncheckedAccount<'info>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = RecoveryConfig::SPACE,
+        seeds = [b"recovery", mint.key().as_ref()],
+        bump
+    )]
+    pub recovery_config: Account<'info, RecoveryConfig>,
+
+    /// CHECK: Extra account meta list of the mint, empty until initialized
+    #[account(
+        mut,
+        seeds = [b"extra-account-metas", mint.key().as_ref()],
+        bump
+    )]
+    pub extra_account_meta_list: UncheckedAccount<'info>,
+
+    /// CHECK: Sanctions config for the mint, empty when no registry is designated
+    #[account(
+        seeds = [b"sanctions-config", mint.key().as_ref()],
+        bump
+    )]
+    pub sanctions_config: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct AuthorizeForcedTransfer<'info> {
+    pub authority: Signer<'info>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        seeds = [b"recovery", mint.key().as_ref()],
+        bump = recovery_config.bump,
+        has_one = authority @ TransferHookError::Unauthorized,
+        has_one = mint,
+    )]
+    pub recovery_config: Account<'info, RecoveryConfig>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeCallerPolicy<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    /// CHECK: Rule authority of the mint, empty while the mint authority configures rules
+    #[account(
+        seeds = [b"rule-authority", mint.key().as_ref()],
+        bump,
+        constraint = is_rule_authority(&mint, &rule_authority, authority.key())?
+            @ TransferHookError::Unauthorized,
+    )]
+    pub rule_authority: UncheckedAccount<'info>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = CallerPolicy::SPACE,
+        seeds = [b"caller-policy", mint.key().as_ref()],
+        bump
+    )]
+    pub caller_policy: Account<'info, CallerPolicy>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateCallerPolicy<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"caller-policy", caller_policy.mint.as_ref()],
+        bump = caller_policy.bump,
+        has_one = authority @ TransferHookError::Unauthorized,
+    )]
+    pub caller_policy: Account<'info, CallerPolicy>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeSellTax<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    /// CHECK: Rule authority of the mint, empty while the mint authority configures rules
+    #[account(
+        seeds = [b"rule-authority", mint.key().as_ref()],
+        bump,
+        constraint = is_rule_authority(&mint, &rule_authority, authority.key())?
+            @ TransferHookError::Unauthorized,
+    )]
+    pub rule_authority: UncheckedAccount<'info>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = SellTaxConfig::SPACE,
+        seeds = [b"sell-tax", mint.key().as_ref()],
+        bump
+    )]
+    pub sell_tax_config: Account<'info, SellTaxConfig>,
+
+    /// CHECK: Confidential transfer policy for the mint, may be empty
+    #[account(
+        seeds = [b"confidential-policy", mint.key().as_ref()],
+        bump
+    )]
+    pub confidential_policy: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateSellTax<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"sell-tax", sell_tax_config.mint.as_ref()],
+        bump = sell_tax_config.bump,
+        has_one = authority @ TransferHookError::Unauthorized,
+    )]
+    pub sell_tax_config: Account<'info, SellTaxConfig>,
+}
+
+#[derive(Accounts)]
+pub struct OpenTaxLedger<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    /// CHECK: Wallet whose sells are recorded in the ledger
+    pub wallet: UncheckedAccount<'info>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = TaxLedger::SPACE,
+        seeds = [b"tax-ledger", mint.key().as_ref(), wallet.key().as_ref()],
+        bump
+    )]
+    pub tax_ledger: Account<'info, TaxLedger>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeConfidentialTransferPolicy<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    /// CHECK: Rule authority of the mint, empty while the mint authority configures rules
+    #[account(
+        seeds = [b"rule-authority", mint.key().as_ref()],
+        bump,
+        constraint = is_rule_authority(&mint, &rule_authority, authority.key())?
+            @ TransferHookError::Unauthorized,
+    )]
+    pub rule_authority: UncheckedAccount<'info>,
+
+    /// CHECK: Transfer limits for the mint, may be empty
+    #[account(
+        seeds = [b"transfer-limits", mint.key().as_ref()],
+        bump
+    )]
+    pub transfer_limits: UncheckedAccount<'info>,
+
+    /// CHECK: Sell tax config for the mint, may be empty
+    #[account(
+        seeds = [b"sell-tax", mint.key().as_ref()],
+        bump
+    )]
+    pub sell_tax_config: UncheckedAccount<'info>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = ConfidentialTransferPolicy::SPACE,
+        seeds = [b"confidential-policy", mint.key().as_ref()],
+        bump
+    )]
+    pub confidential_policy: Account<'info, ConfidentialTransferPolicy>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateConfidentialTransferPolicy<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(address = confidential_policy.mint)]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    /// CHECK: Transfer limits for the mint, may be empty
+    #[account(
+        seeds = [b"transfer-limits", mint.key().as_ref()],
+        bump
+    )]
+    pub transfer_limits: UncheckedAccount<'info>,
+
+    /// CHECK: Sell tax config for the mint, may be empty
+    #[account(
+        seeds = [b"sell-tax", mint.key().as_ref()],
+        bump
+    )]
+    pub sell_tax_config: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"confidential-policy", confidential_policy.mint.as_ref()],
+        bump = confidential_policy.bump,
+        has_one = authority @ TransferHookError::Unauthorized,
+    )]
+    pub confidential_policy: Account<'info, ConfidentialTransferPolicy>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeWhitelist<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    /// CHECK: Rule authority of the mint, empty while the mint authority configures rules
+    #[account(
+        seeds = [b"rule-authority", mint.key().as_ref()],
+        bump,
+        constraint = is_rule_authority(&mint, &rule_authority, authority.key())?
+            @ TransferHookError::Unauthorized,
+    )]
+    pub rule_authority: UncheckedAccount<'info>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = Whitelist::space(0),
+        seeds = [b"whitelist", mint.key().as_ref()],
+        bump
+    )]
+    pub whitelist: Account<'info, Whitelist>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = WhitelistStats::SPACE,
+        seeds = [b"whitelist-stats", mint.key().as_ref()],
+        bump
+    )]
+    pub whitelist_stats: Account<'info, WhitelistStats>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct AddToWhitelist<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    pub mint: InterfaceAccount<'info, Mint>,
+    
+    #[account(
+        seeds = [b"whitelist", mint.key().as_ref()],
+        bump,
+        has_one = authority @ TransferHookError::Unauthorized,
+    )]
+    pub whitelist: Account<'info, Whitelist>,
+    
+    /// CHECK: Account to add to whitelist
+    pub new_account: AccountInfo<'info>,
+    
+    /// CHECK: Whitelist entry for `new_account`, created by the instruction
+    #[account(
+        mut,
+        seeds = [b"whitelist-entry", mint.key().as_ref(), new_account.key().as_ref()],
+        bump
+    )]
+    pub entry: UncheckedAccount<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"whitelist-stats", mint.key().as_ref()],
+        bump = whitelist_stats.bump,
+    )]
+    pub whitelist_stats: Account<'info, WhitelistStats>,
+    
+    /// CHECK: PDA set as the mint's freeze authority
+    #[account(
+        seeds = [b"freeze-authority", mint.key().as_ref()],
+        bump
+    )]
+    pub freeze_authority: UncheckedAccount<'info>,
+    
+    pub token_program: Program<'info, Token2022>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RemoveFromWhitelist<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    pub mint: InterfaceAccount<'info, Mint>,
+    
+    #[account(
+        seeds = [b"whitelist", mint.key().as_ref()],
+        bump,
+        has_one = authority @ TransferHookError::Unauthorized,
+    )]
+    pub whitelist: Account<'info, Whitelist>,
+    
+    /// CHECK: Account to remove from whitelist
+    pub account_to_remove: AccountInfo<'info>,
+    
+    /// CHECK: Whitelist entry for `account_to_remove`, closed by the instruction
+    #[account(
+        mut,
+        seeds = [b"whitelist-entry", mint.key().as_ref(), account_to_remove.key().as_ref()],
+        bump
+    )]
+    pub entry: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"whitelist-stats", mint.key().as_ref()],
+        bump = whitelist_stats.bump,
+    )]
+    pub whitelist_stats: Account<'info, WhitelistStats>,
+
+    /// CHECK: Exemption for `account_to_remove`, whose token accounts stay thawed if it exists
+    #[account(
+        seeds = [b"exempt-authority", account_to_remove.key().as_ref()],
+        bump
+    )]
+    pub exemption: UncheckedAccount<'info>,
+
+    /// CHECK: PDA set as the mint's freeze authority
+    #[account(
+        seeds = [b"freeze-authority", mint.key().as_ref()],
+        bump
+    )]
+    pub freeze_authority: UncheckedAccount<'info>,
+
+    pub token_program: Program<'info, Token2022>,
+}
+
+/// Permissionless crank that brings a token account's freeze state in line
+/// with its owner's whitelist membership
+#[derive(Accounts)]
+pub struct SyncFreezeState<'info> {
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(mut, token::mint = mint)]
+    pub token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(seeds = [b"whitelist", mint.key().as_ref()], bump)]
+    pub whitelist: Account<'info, Whitelist>,
+
+    /// CHECK: Whitelist entry for the token account owner, may be empty
+    #[account(
+        seeds = [b"whitelist-entry", mint.key().as_ref(), token_account.owner.as_ref()],
+        bump
+    )]
+    pub entry: UncheckedAccount<'info>,
+
+    /// CHECK: Exemption for the token account owner, may be empty
+    #[account(
+        seeds = [b"exempt-authority", token_account.owner.as_ref()],
+        bump
+    )]
+    pub exemption: UncheckedAccount<'info>,
+
+    /// CHECK: PDA set as the mint's freeze authority
+    #[account(
+        seeds = [b"freeze-authority", mint.key().as_ref()],
+        bump
+    )]
+    pub freeze_authority: UncheckedAccount<'info>,
+
+    pub token_program: Program<'info, Token2022>,
+}
+
+impl<'info> SyncFreezeState<'info> {
+    fn is_member(&self) -> bool {
+        let exists = |info: &AccountInfo| *info.owner == crate::ID && !info.data_is_empty();
+        exists(&self.entry) || exists(&self.exemption)
+    }
+}
+
+#[derive(Accounts)]
+pub struct RevokeFreezeAuthority<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(mut)]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    /// CHECK: Rule authority of the mint, empty while the mint authority configures rules
+    #[account(
+        seeds = [b"rule-authority", mint.key().as_ref()],
+        bump,
+        constraint = is_rule_authority(&mint, &rule_authority, authority.key())?
+            @ TransferHookError::Unauthorized,
+    )]
+    pub rule_authority: UncheckedAccount<'info>,
+
+    /// CHECK: PDA set as the mint's freeze authority
+    #[account(
+        seeds = [b"freeze-authority", mint.key().as_ref()],
+        bump
+    )]
+    pub freeze_authority: UncheckedAccount<'info>,
+
+    pub token_program: Program<'info, Token2022>,
+}
+
+#[derive(Accounts)]
+pub struct StartWhitelistMigration<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    /// Legacy global whitelist
+    #[account(mut, seeds = [b"whitelist"], bump)]
+    pub whitelist: Account<'info, Whitelist>,
+
+    /// Only needed to claim a legacy whitelist that has no authority
+    #[account(constraint = program.programdata_address()? == program_data.as_ref().map(|data| data.key()))]
+    pub program: Option<Program<'info, crate::program::TransferHook>>,
+
+    pub program_data: Option<Account<'info, ProgramData>>,
+
+    /// Mint whose whitelist receives the legacy members
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    /// CHECK: Rule authority of the mint, empty while the mint authority configures rules
+    #[account(
+        seeds = [b"rule-authority", mint.key().as_ref()],
+        bump,
+        constraint = is_rule_authority(&mint, &rule_authority, authority.key())?
+            @ TransferHookError::Unauthorized,
+    )]
+    pub rule_authority: UncheckedAccount<'info>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = WhitelistMigration::SPACE,
+        seeds = [b"whitelist-migration"],
+        bump
+    )]
+    pub migration: Account<'info, WhitelistMigration>,
+
+    /// CHECK: Whitelist of the mint, created when the migration completes
+    #[account(
+        seeds = [b"whitelist", mint.key().as_ref()],
+        bump,
+        constraint = mint_whitelist.data_is_empty() @ TransferHookError::WhitelistAlreadyMigrated,
+    )]
+    pub mint_whitelist: UncheckedAccount<'info>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = WhitelistStats::SPACE,
+        seeds = [b"whitelist-stats", mint.key().as_ref()],
+        bump
+    )]
+    pub whitelist_stats: Account<'info, WhitelistStats>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateWhitelistEntries<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    /// Legacy global whitelist, closed when the migration completes
+    #[account(
+        mut,
+        seeds = [b"whitelist"],
+        bump,
+        has_one = authority @ TransferHookError::Unauthorized,
+    )]
+    pub whitelist: Account<'info, Whitelist>,
+
+    #[account(
+        mut,
+        seeds = [b"whitelist-migration"],
+        bump = migration.bump,
+    )]
+    pub migration: Account<'info, WhitelistMigration>,
+
+    /// CHECK: Whitelist of the target mint, created when the migration completes
+    #[account(
+        mut,
+        seeds = [b"whitelist", migration.mint.as_ref()],
+        bump
+    )]
+    pub mint_whitelist: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"whitelist-stats", migration.mint.as_ref()],
+        bump = whitelist_stats.bump,
+    )]
+    pub whitelist_stats: Account<'info, WhitelistStats>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(program_id: Pubkey)]
+pub struct RegisterTrustedProgram<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(constraint = program.programdata_address()? == Some(program_data.key()))]
+    pub program: Program<'info, crate::program::TransferHook>,
+
+    #[account(
+        constraint = program_data.upgrade_authority_address == Some(authority.key())
+            @ TransferHookError::Unauthorized,
+    )]
+    pub program_data: Account<'info, ProgramData>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = TrustedProgram::SPACE,
+        seeds = [b"trusted-program", program_id.as_ref()],
+        bump
+    )]
+    pub trusted_program: Account<'info, TrustedProgram>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RegisterExemptAuthority<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// PDA of the trusted program, signed for by that program
+    pub exempt_authority: Signer<'info>,
+
+    #[account(
+        seeds = [b"trusted-program", trusted_program.program_id.as_ref()],
+        bump = trusted_program.bump,
+    )]
+    pub trusted_program: Account<'info, TrustedProgram>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = ExemptAuthority::SPACE,
+        seeds = [b"exempt-authority", exempt_authority.key().as_ref()],
+        bump
+    )]
+    pub exemption: Account<'info, ExemptAuthority>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CreateWhitelistMultisig<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        seeds = [b"whitelist", mint.key().as_ref()],
+        bump,
+        has_one = authority @ TransferHookError::Unauthorized,
+    )]
+    pub whitelist: Account<'info, Whitelist>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = WhitelistMultisig::SPACE,
+        seeds = [b"whitelist-multisig", mint.key().as_ref()],
+        bump
+    )]
+    pub multisig: Account<'info, WhitelistMultisig>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ProposeWhitelistChange<'info> {
+    #[account(mut)]
+    pub proposer: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"whitelist-multisig", multisig.mint.as_ref()],
+        bump = multisig.bump,
+    )]
+    pub multisig: Account<'info, WhitelistMultisig>,
+
+    #[account(
+        init,
+        payer = proposer,
+        space = WhitelistProposal::SPACE,
+        seeds = [
+            b"whitelist-proposal",
+            multisig.key().as_ref(),
+            &multisig.proposal_count.to_le_bytes(),
+        ],
+        bump
+    )]
+    pub proposal: Account<'info, WhitelistProposal>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ApproveWhitelistProposal<'info> {
+    pub signer: Signer<'info>,
+
+    #[account(
+        seeds = [b"whitelist-multisig", multisig.mint.as_ref()],
+        bump = multisig.bump,
+    )]
+    pub multisig: Account<'info, WhitelistMultisig>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"whitelist-proposal",
+            multisig.key().as_ref(),
+            &proposal.index.to_le_bytes(),
+        ],
+        bump = proposal.bump,
+        has_one = multisig,
+    )]
+    pub proposal: Account<'info, WhitelistProposal>,
+}
+
+#[derive(Accounts)]
+pub struct ExecuteWhitelistProposal<'info> {
+    /// Funds a new whitelist entry and receives the rent of a closed one
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(
+        seeds = [b"whitelist-multisig", multisig.mint.as_ref()],
+        bump = multisig.bump,
+    )]
+    pub multisig: Account<'info, WhitelistMultisig>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"whitelist-proposal",
+            multisig.key().as_ref(),
+            &proposal.index.to_le_bytes(),
+        ],
+        bump = proposal.bump,
+        has_one = multisig,
+    )]
+    pub proposal: Account<'info, WhitelistProposal>,
+
+    #[account(address = multisig.mint)]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        seeds = [b"whitelist", multisig.mint.as_ref()],
+        bump,
+        constraint = whitelist.authority == multisig.key() @ TransferHookError::Unauthorized,
+    )]
+    pub whitelist: Account<'info, Whitelist>,
+
+    /// CHECK: Whitelist entry for the proposal's account, created or closed by the instruction
+    #[account(
+        mut,
+        seeds = [b"whitelist-entry", multisig.mint.as_ref(), proposal.account.as_ref()],
+        bump
+    )]
+    pub entry: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"whitelist-stats", multisig.mint.as_ref()],
+        bump = whitelist_stats.bump,
+    )]
+    pub whitelist_stats: Account<'info, WhitelistStats>,
+
+    /// CHECK: Exemption for the proposal's account, whose token accounts stay thawed if it exists
+    #[account(
+        seeds = [b"exempt-authority", proposal.account.as_ref()],
+        bump
+    )]
+    pub exemption: UncheckedAccount<'info>,
+
+    /// CHECK: PDA set as the mint's freeze authority
+    #[account(
+        seeds = [b"freeze-authority", multisig.mint.as_ref()],
+        bump
+    )]
+    pub freeze_authority: UncheckedAccount<'info>,
+
+    pub token_program: Program<'info, Token2022>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeTradingSchedule<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    /// CHECK: Rule authority of the mint, empty while the mint authority configures rules
+    #[account(
+        seeds = [b"rule-authority", mint.key().as_ref()],
+        bump,
+        constraint = is_rule_authority(&mint, &rule_authority, authority.key())?
+            @ TransferHookError::Unauthorized,
+    )]
+    pub rule_authority: UncheckedAccount<'info>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = TradingSchedule::SPACE,
+        seeds = [b"trading-schedule", mint.key().as_ref()],
+        bump
+    )]
+    pub trading_schedule: Account<'info, TradingSchedule>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateTradingSchedule<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"trading-schedule", trading_schedule.mint.as_ref()],
+        bump = trading_schedule.bump,
+        has_one = authority @ TransferHookError::Unauthorized,
+    )]
+    pub trading_schedule: Account<'info, TradingSchedule>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeTransferLimits<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    /// CHECK: Rule authority of the mint, empty while the mint authority configures rules
+    #[account(
+        seeds = [b"rule-authority", mint.key().as_ref()],
+        bump,
+        constraint = is_rule_authority(&mint, &rule_authority, authority.key())?
+            @ TransferHookError::Unauthorized,
+    )]
+    pub rule_authority: UncheckedAccount<'info>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = TransferLimits::SPACE,
+        seeds = [b"transfer-limits", mint.key().as_ref()],
+        bump
+    )]
+    pub transfer_limits: Account<'info, TransferLimits>,
+
+    /// CHECK: Confidential transfer policy for the mint, may be empty
+    #[account(
+        seeds = [b"confidential-policy", mint.key().as_ref()],
+        bump
+    )]
+    pub confidential_policy: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateTransferLimits<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"transfer-limits", transfer_limits.mint.as_ref()],
+        bump = transfer_limits.bump,
+        has_one = authority @ TransferHookError::Unauthorized,
+    )]
+    pub transfer_limits: Account<'info, TransferLimits>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeSanctionsConfig<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    /// CHECK: Rule authority of the mint, empty while the mint authority configures rules
+    #[account(
+        seeds = [b"rule-authority", mint.key().as_ref()],
+        bump,
+        constraint = is_rule_authority(&mint, &rule_authority, authority.key())?
+            @ TransferHookError::Unauthorized,
+    )]
+    pub rule_authority: UncheckedAccount<'info>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = SanctionsConfig::SPACE,
+        seeds = [b"sanctions-config", mint.key().as_ref()],
+        bump
+    )]
+    pub sanctions_config: Account<'info, SanctionsConfig>,
+
+    /// CHECK: Extra account meta list account for transfer hook
+    #[account(
+        mut,
+        seeds = [b"extra-account-metas", mint.key().as_ref()],
+        bump
+    )]
+    pub extra_account_meta_list: AccountInfo<'info>,
+
+    pub system_program: Program<'info, System>,
+
+    /// CHECK: Recovery config for the mint, empty when forced transfers are disabled
+    #[account(
+        seeds = [b"recovery", mint.key().as_ref()],
         bump
     )]
-    pub extra_account_meta_list: UncheckedAccount<'info>,
+    pub recovery_config: UncheckedAccount<'info>,
 }
 
 #[derive(Accounts)]
-pub struct AddToWhitelist<'info> {
+pub struct UpdateSanctionsConfig<'info> {
     #[account(mut)]
     pub authority: Signer<'info>,
-    
+
     #[account(
-        init,
-        payer = authority,
-        space = 1000,
-        seeds = [b"whitelist"],
+        mut,
+        seeds = [b"sanctions-config", sanctions_config.mint.as_ref()],
+        bump = sanctions_config.bump,
+        has_one = authority @ TransferHookError::Unauthorized,
+    )]
+    pub sanctions_config: Account<'info, SanctionsConfig>,
+
+    /// CHECK: Extra account meta list account for transfer hook
+    #[account(
+        mut,
+        seeds = [b"extra-account-metas", sanctions_config.mint.as_ref()],
         bump
     )]
-    pub whitelist: Account<'info, Whitelist>,
-    
-    /// CHECK: Account to add to whitelist
-    pub new_account: AccountInfo<'info>,
-    
+    pub extra_account_meta_list: AccountInfo<'info>,
+
     pub system_program: Program<'info, System>,
+
+    /// CHECK: Recovery config for the mint, empty when forced transfers are disabled
+    #[account(
+        seeds = [b"recovery", sanctions_config.mint.as_ref()],
+        bump
+    )]
+    pub recovery_config: UncheckedAccount<'info>,
 }
 
 #[derive(Accounts)]
-pub struct RemoveFromWhitelist<'info> {
+pub struct InitializeRuleAuthority<'info> {
     #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub mint_authority: Signer<'info>,
+
+    #[account(
+        constraint = mint.mint_authority == COption::Some(mint_authority.key())
+            @ TransferHookError::Unauthorized,
+    )]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = RuleAuthority::SPACE,
+        seeds = [b"rule-authority", mint.key().as_ref()],
+        bump
+    )]
+    pub rule_authority: Account<'info, RuleAuthority>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetRuleAuthority<'info> {
     pub authority: Signer<'info>,
-    
+
     #[account(
         mut,
-        seeds = [b"whitelist"],
-        bump
+        seeds = [b"rule-authority", rule_authority.mint.as_ref()],
+        bump = rule_authority.bump,
+        has_one = authority @ TransferHookError::Unauthorized,
     )]
-    pub whitelist: Account<'info, Whitelist>,
-    
-    /// CHECK: Account to remove from whitelist
-    pub account_to_remove: AccountInfo<'info>,
+    pub rule_authority: Account<'info, RuleAuthority>,
+}
+
+#[derive(Accounts)]
+pub struct GetHookConfig<'info> {
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    /// CHECK: Trading schedule for the mint, may be empty
+    #[account(seeds = [b"trading-schedule", mint.key().as_ref()], bump)]
+    pub trading_schedule: UncheckedAccount<'info>,
+
+    /// CHECK: Transfer limits for the mint, may be empty
+    #[account(seeds = [b"transfer-limits", mint.key().as_ref()], bump)]
+    pub transfer_limits: UncheckedAccount<'info>,
+
+    /// CHECK: Sanctions config for the mint, may be empty
+    #[account(seeds = [b"sanctions-config", mint.key().as_ref()], bump)]
+    pub sanctions_config: UncheckedAccount<'info>,
+
+    /// CHECK: Caller policy for the mint, may be empty
+    #[account(seeds = [b"caller-policy", mint.key().as_ref()], bump)]
+    pub caller_policy: UncheckedAccount<'info>,
+
+    /// CHECK: Sell tax config for the mint, may be empty
+    #[account(seeds = [b"sell-tax", mint.key().as_ref()], bump)]
+    pub sell_tax_config: UncheckedAccount<'info>,
+
+    /// CHECK: Destination whitelist for the mint, may be empty
+    #[account(seeds = [b"whitelist", mint.key().as_ref()], bump)]
+    pub whitelist: UncheckedAccount<'info>,
+
+    /// CHECK: Whitelist member count, empty until the whitelist is created or migrated
+    #[account(seeds = [b"whitelist-stats", mint.key().as_ref()], bump)]
+    pub whitelist_stats: UncheckedAccount<'info>,
+
+    /// CHECK: Confidential transfer policy for the mint, may be empty
+    #[account(seeds = [b"confidential-policy", mint.key().as_ref()], bump)]
+    pub confidential_policy: UncheckedAccount<'info>,
+
+    /// CHECK: Recovery config for the mint, may be empty
+    #[account(seeds = [b"recovery", mint.key().as_ref()], bump)]
+    pub recovery_config: UncheckedAccount<'info>,
+}
+
+/// Return value of `get_hook_config`. Each rule is enabled exactly when its
+/// authority is `Some`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct HookConfig {
+    pub version: u32, // HOOK_VERSION of the deployed program
+    pub paused: bool, // Trading schedule currently rejects transfers
+    pub whitelist_size: u32,
+    pub whitelist_authority: Option<Pubkey>,
+    pub trading_schedule_authority: Option<Pubkey>,
+    pub transfer_limits_authority: Option<Pubkey>,
+    pub sanctions_authority: Option<Pubkey>,
+    pub caller_policy_authority: Option<Pubkey>,
+    pub sell_tax_authority: Option<Pubkey>,
+    pub confidential_transfers_allowed: bool,
+    pub recovery_authority: Option<Pubkey>,
+    pub freeze_authority: Option<Pubkey>,
 }
 
 // State Accounts
+/// Per-mint whitelists at `[b"whitelist", mint]` keep `accounts` empty and
+/// store members as `WhitelistEntry` accounts. Only the legacy global
+/// whitelist at `[b"whitelist"]` holds members inline, until migrated.
 #[account]
 pub struct Whitelist {
     pub authority: Pubkey,
     pub accounts: Vec<Pubkey>,
 }
 
+impl Whitelist {
+    pub const fn space(num_accounts: usize) -> usize {
+        8 + 32 + 4 + 32 * num_accounts
+    }
+}
+
+#[account]
+pub struct WhitelistEntry {
+    pub mint: Pubkey,
+    pub wallet: Pubkey,
+    pub bump: u8,
+}
+
+impl WhitelistEntry {
+    pub const SPACE: usize = 8 + 32 * 2 + 1; // 8 + 64 + 1 = 73 bytes
+}
+
+/// Number of whitelist members, which can no longer be read off the
+/// whitelist account once membership lives in per-member entries
+#[account]
+pub struct WhitelistStats {
+    pub member_count: u32,
+    pub bump: u8,
+}
+
+impl WhitelistStats {
+    pub const SPACE: usize = 8 + 4 + 1; // 8 + 4 + 1 = 13 bytes
+}
+
+#[account]
+pub struct WhitelistMigration {
+    pub mint: Pubkey, // Mint whose whitelist receives the legacy members
+    pub total: u32,
+    pub next_index: u32, // Next legacy member without an entry
+    pub completed: bool,
+    pub bump: u8,
+}
+
+impl WhitelistMigration {
+    pub const SPACE: usize = 8 + 32 + 4 + 4 + 1 + 1; // 8 + 32 + 4 + 4 + 1 + 1 = 50 bytes
+}
+
+#[account]
+pub struct TrustedProgram {
+    pub program_id: Pubkey,
+    pub bump: u8,
+}
+
+impl TrustedProgram {
+    pub const SPACE: usize = 8 + 32 + 1; // 8 + 32 + 1 = 41 bytes
+}
+
+#[account]
+pub struct ExemptAuthority {
+    pub authority: Pubkey,
+    pub program: Pubkey, // Trusted program that registered the exemption
+    pub bump: u8,
+}
+
+impl ExemptAuthority {
+    pub const SPACE: usize = 8 + 32 * 2 + 1; // 8 + 64 + 1 = 73 bytes
+}
+
+#[account]
+pub struct WhitelistMultisig {
+    pub mint: Pubkey,
+    pub signers: Vec<Pubkey>,
+    pub threshold: u8,
+    pub proposal_count: u64,
+    pub bump: u8,
+}
+
+impl WhitelistMultisig {
+    pub const MAX_SIGNERS: usize = 10;
+    pub const SPACE: usize = 8 + 32 + (4 + 32 * Self::MAX_SIGNERS) + 1 + 8 + 1; // 8 + 32 + 324 + 1 + 8 + 1 = 374 bytes
+
+    fn validate(signers: &[Pubkey], threshold: u8) -> Result<()> {
+        require!(
+            !signers.is_empty() && signers.len() <= Self::MAX_SIGNERS,
+            TransferHookError::InvalidMultisigSigners
+        );
+        for (i, signer) in signers.iter().enumerate() {
+            require!(
+                !signers[..i].contains(signer),
+                TransferHookError::InvalidMultisigSigners
+            );
+        }
+        require!(
+            threshold > 0 && threshold as usize <= signers.len(),
+            TransferHookError::InvalidMultisigThreshold
+        );
+        Ok(())
+    }
+
+    fn is_signer(&self, key: &Pubkey) -> bool {
+        self.signers.contains(key)
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum WhitelistAction {
+    Add,
+    Remove,
+}
+
+#[account]
+pub struct WhitelistProposal {
+    pub multisig: Pubkey,
+    pub index: u64,
+    pub action: WhitelistAction,
+    pub account: Pubkey,
+    pub approvals: Vec<Pubkey>,
+    pub executed: bool,
+    pub bump: u8,
+}
+
+impl WhitelistProposal {
+    pub const SPACE: usize = 8 + 32 + 8 + 1 + 32 + (4 + 32 * WhitelistMultisig::MAX_SIGNERS) + 1 + 1; // 8 + 32 + 8 + 1 + 32 + 324 + 1 + 1 = 407 bytes
+}
+
+const SECONDS_PER_DAY: i64 = 86_400;
+
+/// Daily trading window in seconds after 00:00 UTC. Trading is allowed for
+/// `open <= t < close`; a window with `open == close` closes the market for
+/// that weekday.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default)]
+pub struct TradingWindow {
+    pub open: u32,
+    pub close: u32,
+}
+
+#[account]
+pub struct TradingSchedule {
+    pub authority: Pubkey,
+    pub mint: Pubkey,
+    pub weekly_windows: [TradingWindow; 7], // Monday first
+    pub holidays: Vec<u32>,                 // UTC days since the Unix epoch
+    pub bump: u8,
+}
+
+impl TradingSchedule {
+    pub const MAX_HOLIDAYS: usize = 64;
+    pub const SPACE: usize = 8 + 32 * 2 + 8 * 7 + (4 + 4 * Self::MAX_HOLIDAYS) + 1; // 8 + 64 + 56 + 260 + 1 = 389 bytes
+
+    fn validate(weekly_windows: &[TradingWindow; 7], holidays: &[u32]) -> Result<()> {
+        require!(
+            holidays.len() <= Self::MAX_HOLIDAYS,
+            TransferHookError::TooManyHolidays
+        );
+        for window in weekly_windows {
+            require!(
+                window.open <= window.close && window.close as i64 <= SECONDS_PER_DAY,
+                TransferHookError::InvalidTradingWindow
+            );
+        }
+        Ok(())
+    }
+
+    /// Whether transfers are permitted at `unix_timestamp`
+    pub fn is_open(&self, unix_timestamp: i64) -> bool {
+        let day = unix_timestamp.div_euclid(SECONDS_PER_DAY);
+        if self.holidays.iter().any(|&holiday| holiday as i64 == day) {
+            return false;
+        }
+
+        // 1970-01-01 was a Thursday, which is index 3 counting from Monday
+        let weekday = (day + 3).rem_euclid(7) as usize;
+        let second = unix_timestamp.rem_euclid(SECONDS_PER_DAY) as u32;
+        let window = &self.weekly_windows[weekday];
+        second >= window.open && second < window.close
+    }
+}
+
+#[account]
+pub struct TransferLimits {
+    pub authority: Pubkey,
+    pub mint: Pubkey,
+    pub min_amount: u64,
+    pub exempt_programs: Vec<Pubkey>, // Programs whose exempt authorities skip the minimum
+    pub bump: u8,
+}
+
+impl TransferLimits {
+    pub const MAX_EXEMPT_PROGRAMS: usize = 8;
+    pub const SPACE: usize = 8 + 32 * 2 + 8 + (4 + 32 * Self::MAX_EXEMPT_PROGRAMS) + 1; // 8 + 64 + 8 + 260 + 1 = 341 bytes
+
+    fn validate(exempt_programs: &[Pubkey]) -> Result<()> {
+        require!(
+            exempt_programs.len() <= Self::MAX_EXEMPT_PROGRAMS,
+            TransferHookError::TooManyExemptPrograms
+        );
+        Ok(())
+    }
+}
+
+/// Points a mint at an externally maintained sanctions registry. The registry
+/// account holds, starting at `data_offset`, a little-endian `u32` count
+/// followed by that many owner keys sorted in ascending byte order.
+#[account]
+pub struct SanctionsConfig {
+    pub authority: Pubkey,
+    pub mint: Pubkey,
+    pub registry: Pubkey,
+    pub registry_program: Pubkey, // Required owner of the registry account
+    pub data_offset: u32,
+    pub bump: u8,
+}
+
+impl SanctionsConfig {
+    pub const SPACE: usize = 8 + 32 * 4 + 4 + 1; // 8 + 128 + 4 + 1 = 141 bytes
+
+    /// Binary search the registry's sorted key list for `key`
+    fn is_listed(&self, registry_data: &[u8], key: &Pubkey) -> Result<bool> {
+        let offset = self.data_offset as usize;
+        let count_bytes = registry_data
+            .get(offset..offset + 4)
+            .ok_or(TransferHookError::InvalidSanctionsRegistry)?;
+        let count = u32::from_le_bytes(count_bytes.try_into().unwrap()) as usize;
+        let keys = registry_data
+            .get(offset + 4..offset + 4 + count * 32)
+            .ok_or(TransferHookError::InvalidSanctionsRegistry)?;
+
+        let (mut low, mut high) = (0, count);
+        while low < high {
+            let mid = low + (high - low) / 2;
+            match keys[mid * 32..(mid + 1) * 32].cmp(key.as_ref()) {
+                std::cmp::Ordering::Equal => return Ok(true),
+                std::cmp::Ordering::Less => low = mid + 1,
+                std::cmp::Ordering::Greater => high = mid,
+            }
+        }
+        Ok(false)
+    }
+}
+
+#[account]
+pub struct CallerPolicy {
+    pub authority: Pubkey,
+    pub mint: Pubkey,
+    pub approved_programs: Vec<Pubkey>,
+    pub bump: u8,
+}
+
+impl CallerPolicy {
+    pub const MAX_APPROVED_PROGRAMS: usize = 8;
+    pub const SPACE: usize = 8 + 32 * 2 + (4 + 32 * Self::MAX_APPROVED_PROGRAMS) + 1; // 8 + 64 + 260 + 1 = 333 bytes
+
+    fn validate(approved_programs: &[Pubkey]) -> Result<()> {
+        require!(
+            approved_programs.len() <= Self::MAX_APPROVED_PROGRAMS,
+            TransferHookError::TooManyApprovedPrograms
+        );
+        Ok(())
+    }
+
+    fn is_approved(&self, program_id: &Pubkey) -> bool {
+        self.approved_programs.contains(program_id)
+    }
+}
+
+#[account]
+pub struct SellTaxConfig {
+    pub authority: Pubkey,
+    pub mint: Pubkey,
+    pub tax_bps: u16,
+    pub collector: Pubkey, // Owner of the token account receiving settlements
+    pub bump: u8,
+}
+
+impl SellTaxConfig {
+    pub const SPACE: usize = 8 + 32 * 2 + 2 + 32 + 1; // 8 + 64 + 2 + 32 + 1 = 107 bytes
+}
+
+#[account]
+pub struct TaxLedger {
+    pub mint: Pubkey,
+    pub wallet: Pubkey,
+    pub owed: u64,
+    pub bump: u8,
+}
+
+impl TaxLedger {
+    pub const SPACE: usize = 8 + 32 * 2 + 8 + 1; // 8 + 64 + 8 + 1 = 81 bytes
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct ForcedTransfer {
+    pub source: Pubkey,      // Source token account
+    pub destination: Pubkey, // Destination token account
+    pub amount: u64,
+}
+
+#[account]
+pub struct RecoveryConfig {
+    pub authority: Pubkey,
+    pub mint: Pubkey,
+    pub delegate: Pubkey, // Mint's permanent delegate at initialization
+    pub pending: Option<ForcedTransfer>,
+    pub bump: u8,
+}
+
+impl RecoveryConfig {
+    pub const SPACE: usize = 8 + 32 * 3 + (1 + 32 * 2 + 8) + 1; // 8 + 96 + 73 + 1 = 178 bytes
+
+    /// Clear the pending forced transfer if `transfer`, signed by
+    /// `authority`, is exactly the one staged for the delegate
+    pub fn take_staged(&mut self, authority: &Pubkey, transfer: &ForcedTransfer) -> bool {
+        if *authority != self.delegate || self.pending.as_ref() != Some(transfer) {
+            return false;
+        }
+        self.pending = None;
+        true
+    }
+}
+
+/// Audit record of a forced transfer executed through the hook
+#[event]
+pub struct ForcedTransferEvent {
+    pub mint: Pubkey,
+    pub source: Pubkey,
+    pub destination: Pubkey,
+    pub amount: u64,
+    pub recovery_authority: Pubkey,
+    pub delegate: Pubkey,
+    pub timestamp: i64,
+}
+
+#[account]
+pub struct ConfidentialTransferPolicy {
+    pub authority: Pubkey,
+    pub mint: Pubkey,
+    pub allow_confidential_transfers: bool,
+    pub bump: u8,
+}
+
+impl ConfidentialTransferPolicy {
+    pub const SPACE: usize = 8 + 32 * 2 + 1 + 1; // 8 + 64 + 1 + 1 = 74 bytes
+}
+
+/// Configures a mint's hook rules in place of its mint authority
+#[account]
+pub struct RuleAuthority {
+    pub mint: Pubkey,
+    pub authority: Pubkey,
+    pub bump: u8,
+}
+
+impl RuleAuthority {
+    pub const SPACE: usize = 8 + 32 * 2 + 1; // 8 + 64 + 1 = 73 bytes
+}
+
 // Custom Errors
 #[error_code]
 pub enum TransferHookError {
@@ -147,4 +3108,307 @@ pub enum TransferHookError {
     AccountNotWhitelisted,
     #[msg("Whitelist is full")]
     WhitelistFull,
+    #[msg("Signer is not authorized for this mint")]
+    Unauthorized,
+    #[msg("Transfers are not permitted outside trading hours")]
+    OutsideTradingHours,
+    #[msg("Trading window must satisfy open <= close <= 86400")]
+    InvalidTradingWindow,
+    #[msg("Too many holidays in trading schedule")]
+    TooManyHolidays,
+    #[msg("Transfer amount is below the mint's minimum")]
+    TransferBelowMinimum,
+    #[msg("Too many exempt programs in transfer limits")]
+    TooManyExemptPrograms,
+    #[msg("Multisig signers must be unique and at most 10")]
+    InvalidMultisigSigners,
+    #[msg("Multisig threshold must be between 1 and the number of signers")]
+    InvalidMultisigThreshold,
+    #[msg("Signer is not a member of the whitelist multisig")]
+    NotMultisigSigner,
+    #[msg("Proposal has already been approved by this signer")]
+    ProposalAlreadyApproved,
+    #[msg("Proposal has already been executed")]
+    ProposalAlreadyExecuted,
+    #[msg("Proposal has not reached the approval threshold")]
+    ThresholdNotMet,
+    #[msg("Sanctions registry account was not provided")]
+    SanctionsRegistryMissing,
+    #[msg("Sanctions registry account is invalid")]
+    InvalidSanctionsRegistry,
+    #[msg("Transfer involves a sanctioned owner")]
+    SanctionedParty,
+    #[msg("Exempt authority is not a PDA of the trusted program")]
+    UntrustedProgram,
+    #[msg("Whitelist has already been migrated")]
+    WhitelistAlreadyMigrated,
+    #[msg("Migration chunk extends past the legacy whitelist")]
+    MigrationChunkOutOfRange,
+    #[msg("Whitelist entry account does not match the legacy member")]
+    InvalidWhitelistEntry,
+    #[msg("Tax rate cannot exceed 10000 basis points")]
+    InvalidTaxRate,
+    #[msg("Seller has no tax ledger for this mint")]
+    TaxLedgerMissing,
+    #[msg("Settlement exceeds the tax owed by the wallet")]
+    SettlementExceedsOwedTax,
+    #[msg("Tax owed by the wallet overflowed")]
+    TaxOverflow,
+    #[msg("Hook was not invoked by a Token-2022 transfer")]
+    NotTransferring,
+    #[msg("Transfer was not made through an approved program")]
+    CallerNotApproved,
+    #[msg("Too many approved programs in caller policy")]
+    TooManyApprovedPrograms,
+    #[msg("Token account owner is still whitelisted")]
+    AccountStillWhitelisted,
+    #[msg("Confidential transfers cannot be combined with amount-based rules")]
+    ConfidentialTransfersWithAmountRules,
+    #[msg("Mint has no permanent delegate")]
+    PermanentDelegateMissing,
+    #[msg("Whitelist member count overflowed")]
+    MemberCountOverflow,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 1970-01-05, the first Monday after the epoch
+    const MONDAY: i64 = 4 * SECONDS_PER_DAY;
+
+    fn schedule(weekly_windows: [TradingWindow; 7], holidays: Vec<u32>) -> TradingSchedule {
+        TradingSchedule {
+            authority: Pubkey::default(),
+            mint: Pubkey::default(),
+            weekly_windows,
+            holidays,
+            bump: 0,
+        }
+    }
+
+    fn closed_week() -> [TradingWindow; 7] {
+        [(); 7].map(|_| TradingWindow { open: 0, close: 0 })
+    }
+
+    #[test]
+    fn schedule_uses_monday_first_weekdays() {
+        let mut windows = closed_week();
+        windows[3] = TradingWindow { open: 0, close: 86_400 }; // Thursday
+        let schedule = schedule(windows, Vec::new());
+
+        // 1970-01-01 was a Thursday
+        assert!(schedule.is_open(0));
+        assert!(!schedule.is_open(MONDAY));
+        assert!(schedule.is_open(MONDAY + 3 * SECONDS_PER_DAY));
+        assert!(!schedule.is_open(-1)); // Wednesday
+        assert!(schedule.is_open(-7 * SECONDS_PER_DAY)); // The Thursday before
+    }
+
+    #[test]
+    fn schedule_window_is_half_open() {
+        let mut windows = closed_week();
+        windows[0] = TradingWindow { open: 3_600, close: 7_200 };
+        let schedule = schedule(windows, Vec::new());
+
+        assert!(!schedule.is_open(MONDAY + 3_599));
+        assert!(schedule.is_open(MONDAY + 3_600));
+        assert!(schedule.is_open(MONDAY + 7_199));
+        assert!(!schedule.is_open(MONDAY + 7_200));
+    }
+
+    #[test]
+    fn schedule_close_at_midnight_covers_last_second() {
+        let mut windows = closed_week();
+        windows[0] = TradingWindow { open: 0, close: 86_400 };
+        let schedule = schedule(windows, Vec::new());
+
+        assert!(schedule.is_open(MONDAY + 86_399));
+        assert!(!schedule.is_open(MONDAY + 86_400)); // Tuesday
+    }
+
+    #[test]
+    fn schedule_is_closed_on_holidays() {
+        let windows = [(); 7].map(|_| TradingWindow { open: 0, close: 86_400 });
+        let schedule = schedule(windows, vec![4]);
+
+        assert!(schedule.is_open(MONDAY - 1));
+        assert!(!schedule.is_open(MONDAY));
+        assert!(!schedule.is_open(MONDAY + 86_399));
+        assert!(schedule.is_open(MONDAY + 86_400));
+    }
+
+    #[test]
+    fn schedule_validation_rejects_bad_windows() {
+        let mut windows = closed_week();
+        assert!(TradingSchedule::validate(&windows, &[]).is_ok());
+
+        windows[0] = TradingWindow { open: 0, close: 86_401 };
+        assert!(TradingSchedule::validate(&windows, &[]).is_err());
+
+        windows[0] = TradingWindow { open: 10, close: 5 };
+        assert!(TradingSchedule::validate(&windows, &[]).is_err());
+
+        let holidays = vec![0; TradingSchedule::MAX_HOLIDAYS + 1];
+        assert!(TradingSchedule::validate(&closed_week(), &holidays).is_err());
+    }
+
+    fn sanctions_config(data_offset: u32) -> SanctionsConfig {
+        SanctionsConfig {
+            authority: Pubkey::default(),
+            mint: Pubkey::default(),
+            registry: Pubkey::default(),
+            registry_program: Pubkey::default(),
+            data_offset,
+            bump: 0,
+        }
+    }
+
+    fn registry_data(prefix_len: usize, count: u32, keys: &[Pubkey]) -> Vec<u8> {
+        let mut data = vec![0xff; prefix_len];
+        data.extend_from_slice(&count.to_le_bytes());
+        for key in keys {
+            data.extend_from_slice(key.as_ref());
+        }
+        data
+    }
+
+    #[test]
+    fn sanctions_finds_listed_keys() {
+        let keys = [1u8, 5, 9].map(|byte| Pubkey::new_from_array([byte; 32]));
+        let config = sanctions_config(8);
+        let data = registry_data(8, 3, &keys);
+
+        for key in &keys {
+            assert!(config.is_listed(&data, key).unwrap());
+        }
+        for byte in [0u8, 3, 7, 10] {
+            let key = Pubkey::new_from_array([byte; 32]);
+            assert!(!config.is_listed(&data, &key).unwrap());
+        }
+    }
+
+    #[test]
+    fn sanctions_empty_registry_lists_nobody() {
+        let config = sanctions_config(0);
+        let data = registry_data(0, 0, &[]);
+        assert!(!config.is_listed(&data, &Pubkey::default()).unwrap());
+    }
+
+    #[test]
+    fn sanctions_rejects_truncated_registry() {
+        let keys = [Pubkey::new_from_array([1; 32])];
+        let key = Pubkey::default();
+
+        // Count claims more keys than the account holds
+        let config = sanctions_config(0);
+        let data = registry_data(0, 2, &keys);
+        assert_eq!(
+            config.is_listed(&data, &key).unwrap_err(),
+            TransferHookError::InvalidSanctionsRegistry.into()
+        );
+
+        // Offset points past the end of the account
+        let config = sanctions_config(64);
+        let data = registry_data(0, 1, &keys);
+        assert_eq!(
+            config.is_listed(&data, &key).unwrap_err(),
+            TransferHookError::InvalidSanctionsRegistry.into()
+        );
+    }
+
+    fn signers(count: u8) -> Vec<Pubkey> {
+        (1..=count).map(|byte| Pubkey::new_from_array([byte; 32])).collect()
+    }
+
+    #[test]
+    fn multisig_accepts_valid_configs() {
+        assert!(WhitelistMultisig::validate(&signers(1), 1).is_ok());
+        assert!(WhitelistMultisig::validate(&signers(3), 2).is_ok());
+        assert!(WhitelistMultisig::validate(&signers(10), 10).is_ok());
+    }
+
+    #[test]
+    fn multisig_rejects_bad_signers() {
+        assert_eq!(
+            WhitelistMultisig::validate(&[], 1).unwrap_err(),
+            TransferHookError::InvalidMultisigSigners.into()
+        );
+        assert_eq!(
+            WhitelistMultisig::validate(&signers(11), 1).unwrap_err(),
+            TransferHookError::InvalidMultisigSigners.into()
+        );
+
+        let mut duplicated = signers(3);
+        duplicated[2] = duplicated[0];
+        assert_eq!(
+            WhitelistMultisig::validate(&duplicated, 1).unwrap_err(),
+            TransferHookError::InvalidMultisigSigners.into()
+        );
+    }
+
+    #[test]
+    fn multisig_rejects_bad_thresholds() {
+        assert_eq!(
+            WhitelistMultisig::validate(&signers(3), 0).unwrap_err(),
+            TransferHookError::InvalidMultisigThreshold.into()
+        );
+        assert_eq!(
+            WhitelistMultisig::validate(&signers(3), 4).unwrap_err(),
+            TransferHookError::InvalidMultisigThreshold.into()
+        );
+    }
+
+    fn recovery(pending: Option<ForcedTransfer>) -> RecoveryConfig {
+        RecoveryConfig {
+            authority: Pubkey::new_unique(),
+            mint: Pubkey::new_unique(),
+            delegate: Pubkey::new_unique(),
+            pending,
+            bump: 0,
+        }
+    }
+
+    fn forced_transfer() -> ForcedTransfer {
+        ForcedTransfer {
+            source: Pubkey::new_unique(),
+            destination: Pubkey::new_unique(),
+            amount: 1_000,
+        }
+    }
+
+    #[test]
+    fn recovery_takes_the_staged_transfer_once() {
+        let staged = forced_transfer();
+        let mut config = recovery(Some(staged));
+        let delegate = config.delegate;
+
+        assert!(config.take_staged(&delegate, &staged));
+        assert_eq!(config.pending, None);
+        assert!(!config.take_staged(&delegate, &staged));
+    }
+
+    #[test]
+    fn recovery_ignores_transfers_differing_from_the_staged_one() {
+        let staged = forced_transfer();
+        let mut config = recovery(Some(staged));
+        let delegate = config.delegate;
+
+        let other_source = ForcedTransfer { source: Pubkey::new_unique(), ..staged };
+        let other_destination = ForcedTransfer { destination: Pubkey::new_unique(), ..staged };
+        let other_amount = ForcedTransfer { amount: staged.amount + 1, ..staged };
+        for transfer in [other_source, other_destination, other_amount] {
+            assert!(!config.take_staged(&delegate, &transfer));
+        }
+        assert!(!config.take_staged(&Pubkey::new_unique(), &staged));
+        assert_eq!(config.pending, Some(staged));
+    }
+
+    #[test]
+    fn recovery_ignores_transfers_when_nothing_is_staged() {
+        let mut config = recovery(None);
+        let delegate = config.delegate;
+
+        assert!(!config.take_staged(&delegate, &forced_transfer()));
+    }
 }
@@ -1,16 +1,838 @@
 import * as anchor from "@coral-xyz/anchor";
-import { Program } from "@coral-xyz/anchor";
-import { Token2022AmmHackathon } from "../target/types/token2022_amm_hackathon";
+import { BN, Program, web3 } from "@coral-xyz/anchor";
+import { expect } from "chai";
+import { Token2022Amm } from "../target/types/token2022_amm";
+import { TransferHook } from "../target/types/transfer_hook";
+
+type AccountMeta = web3.AccountMeta;
+type Keypair = web3.Keypair;
+type PublicKey = web3.PublicKey;
+const {
+  ComputeBudgetProgram,
+  Keypair,
+  PublicKey,
+  SystemProgram,
+  SYSVAR_INSTRUCTIONS_PUBKEY,
+  SYSVAR_RENT_PUBKEY,
+  Transaction,
+  TransactionInstruction,
+} = web3;
+const { ASSOCIATED_PROGRAM_ID, TOKEN_PROGRAM_ID } = anchor.utils.token;
+
+const TOKEN_2022_PROGRAM_ID = new PublicKey(
+  "TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb"
+);
+const BPF_LOADER_UPGRADEABLE_ID = new PublicKey(
+  "BPFLoaderUpgradeab1e11111111111111111111111"
+);
+const DECIMALS = 6;
 
 describe("token2022-amm-hackathon", () => {
   // Configure the client to use the local cluster.
-  anchor.setProvider(anchor.AnchorProvider.env());
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const amm = anchor.workspace.token2022Amm as Program<Token2022Amm>;
+  const hook = anchor.workspace.transferHook as Program<TransferHook>;
+  const connection = provider.connection;
+  const payer = (provider.wallet as anchor.Wallet).payer;
+
+  const findPda = (programId: PublicKey, seeds: (string | PublicKey)[]) =>
+    PublicKey.findProgramAddressSync(
+      seeds.map((seed) =>
+        typeof seed === "string" ? Buffer.from(seed) : seed.toBuffer()
+      ),
+      programId
+    )[0];
+  const hookPda = (...seeds: (string | PublicKey)[]) =>
+    findPda(hook.programId, seeds);
+  const ammPda = (...seeds: (string | PublicKey)[]) =>
+    findPda(amm.programId, seeds);
+  const ata = (
+    mint: PublicKey,
+    owner: PublicKey,
+    tokenProgram = TOKEN_2022_PROGRAM_ID
+  ) => findPda(ASSOCIATED_PROGRAM_ID, [owner, tokenProgram, mint]);
+
+  const computeBudget = ComputeBudgetProgram.setComputeUnitLimit({
+    units: 600_000,
+  });
+  const send = (
+    instructions: web3.TransactionInstruction[],
+    signers: Keypair[] = []
+  ) =>
+    provider.sendAndConfirm(
+      new Transaction().add(computeBudget, ...instructions),
+      signers
+    );
+
+  const expectFailure = async (promise: Promise<unknown>, code: string) => {
+    try {
+      await promise;
+    } catch (err) {
+      const logs: string[] = (err as { logs?: string[] }).logs ?? [];
+      expect(`${err}\n${logs.join("\n")}`).to.include(code);
+      return;
+    }
+    expect.fail(`expected the transaction to fail with ${code}`);
+  };
+
+  const balance = async (tokenAccount: PublicKey) =>
+    Number((await connection.getTokenAccountBalance(tokenAccount)).value.amount);
+  const isFrozen = async (tokenAccount: PublicKey) => {
+    const { value } = await connection.getParsedAccountInfo(tokenAccount);
+    return (value.data as web3.ParsedAccountData).parsed.info.state === "frozen";
+  };
+
+  // Create the owner's associated token account for the mint if missing
+  const createAta = async (
+    mint: PublicKey,
+    owner: PublicKey,
+    tokenProgram = TOKEN_2022_PROGRAM_ID
+  ) => {
+    const address = ata(mint, owner, tokenProgram);
+    await send([
+      new TransactionInstruction({
+        programId: ASSOCIATED_PROGRAM_ID,
+        keys: [
+          { pubkey: payer.publicKey, isSigner: true, isWritable: true },
+          { pubkey: address, isSigner: false, isWritable: true },
+          { pubkey: owner, isSigner: false, isWritable: false },
+          { pubkey: mint, isSigner: false, isWritable: false },
+          { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+          { pubkey: tokenProgram, isSigner: false, isWritable: false },
+        ],
+        data: Buffer.from([1]), // CreateIdempotent
+      }),
+    ]);
+    return address;
+  };
+
+  // Extra accounts the hook's ExtraAccountMetaList names for a transfer of
+  // `mint`, followed by the hook program and the list itself. Token-2022 and
+  // the AMM pick them out by key, so lists for several transfers can be
+  // passed together.
+  const hookAccounts = (
+    mint: PublicKey,
+    sourceOwner: PublicKey,
+    destinationOwner: PublicKey,
+    authority = sourceOwner
+  ): AccountMeta[] => {
+    // The hook writes to the tax ledger and, once recovery is configured, to
+    // the recovery config
+    const written = [
+      hookPda("tax-ledger", mint, sourceOwner),
+      hookPda("recovery", mint),
+    ];
+    return [
+      hookPda("trading-schedule", mint),
+      hookPda("transfer-limits", mint),
+      hookPda("sanctions-config", mint),
+      hookPda("whitelist", mint),
+      hookPda("exempt-authority", destinationOwner),
+      hookPda("whitelist-entry", mint, destinationOwner),
+      hookPda("sell-tax", mint),
+      hookPda("tax-ledger", mint, sourceOwner),
+      hookPda("caller-policy", mint),
+      SYSVAR_INSTRUCTIONS_PUBKEY,
+      hookPda("recovery", mint),
+      hookPda("exempt-authority", authority),
+      hook.programId,
+      hookPda("extra-account-metas", mint),
+    ].map((pubkey) => ({
+      pubkey,
+      isSigner: false,
+      isWritable: written.some((key) => key.equals(pubkey)),
+    }));
+  };
+
+  // Accounts needed by several transfers, writable if any of them needs it
+  const combine = (...lists: AccountMeta[][]) => {
+    const merged = new Map<string, AccountMeta>();
+    for (const meta of ([] as AccountMeta[]).concat(...lists)) {
+      const key = meta.pubkey.toBase58();
+      const isWritable = meta.isWritable || (merged.get(key)?.isWritable ?? false);
+      merged.set(key, { ...meta, isWritable });
+    }
+    return Array.from(merged.values());
+  };
+
+  // Token-2022 `TransferChecked` between the owners' associated token
+  // accounts, or into `destination` when given, signed by `authority`
+  const transferInstruction = (
+    mint: PublicKey,
+    sourceOwner: PublicKey,
+    destinationOwner: PublicKey,
+    amount: number,
+    destination = ata(mint, destinationOwner),
+    authority = sourceOwner
+  ) =>
+    new TransactionInstruction({
+      programId: TOKEN_2022_PROGRAM_ID,
+      keys: [
+        { pubkey: ata(mint, sourceOwner), isSigner: false, isWritable: true },
+        { pubkey: mint, isSigner: false, isWritable: false },
+        { pubkey: destination, isSigner: false, isWritable: true },
+        { pubkey: authority, isSigner: true, isWritable: false },
+        ...hookAccounts(mint, sourceOwner, destinationOwner, authority),
+      ],
+      data: Buffer.concat([
+        Buffer.from([12]), // TransferChecked
+        new BN(amount).toArrayLike(Buffer, "le", 8),
+        Buffer.from([DECIMALS]),
+      ]),
+    });
+
+  // Create a mint using the transfer hook program, with its whole supply
+  // minted to the provider wallet
+  const createHookedMint = async (
+    supply: number,
+    {
+      recovery = false,
+      permanentDelegate = null,
+    }: { recovery?: boolean; permanentDelegate?: PublicKey | null } = {}
+  ) => {
+    const mint = Keypair.generate();
+    await amm.methods
+      .createTokenWithHook(
+        { name: "Hooked", symbol: "HOOK", uri: "", additionalMetadata: [] },
+        DECIMALS,
+        new BN(supply),
+        {
+          transferFee: null,
+          interestRate: null,
+          defaultAccountFrozen: false,
+          confidentialTransfers: false,
+          recovery,
+          permanentDelegate,
+          nonTransferable: false,
+          closeAuthority: null,
+          groupPointer: null,
+        },
+        null
+      )
+      .accountsPartial({
+        payer: payer.publicKey,
+        mint: mint.publicKey,
+        transferHookProgram: hook.programId,
+        supplyConfig: null,
+        payerTokenAccount: ata(mint.publicKey, payer.publicKey),
+        tokenProgram: TOKEN_2022_PROGRAM_ID,
+        associatedTokenProgram: ASSOCIATED_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+        rent: SYSVAR_RENT_PUBKEY,
+        groupMint: null,
+        extraAccountMetaList: hookPda("extra-account-metas", mint.publicKey),
+        hookSanctionsConfig: hookPda("sanctions-config", mint.publicKey),
+        hookRecoveryConfig: hookPda("recovery", mint.publicKey),
+        hookTransferLimits: null,
+        hookSellTaxConfig: null,
+        confidentialPolicy: null,
+        hookRuleAuthority: null,
+      })
+      .preInstructions([computeBudget])
+      .signers([mint])
+      .rpc();
+    return mint.publicKey;
+  };
+
+  const initializeWhitelist = (mint: PublicKey) =>
+    hook.methods
+      .initializeWhitelist()
+      .accountsPartial({
+        authority: payer.publicKey,
+        mint,
+        ruleAuthority: hookPda("rule-authority", mint),
+        whitelist: hookPda("whitelist", mint),
+        whitelistStats: hookPda("whitelist-stats", mint),
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc();
+
+  // Accounts shared by `add_to_whitelist` and `remove_from_whitelist`
+  const whitelistAccounts = (mint: PublicKey, wallet: PublicKey) => ({
+    authority: payer.publicKey,
+    mint,
+    whitelist: hookPda("whitelist", mint),
+    entry: hookPda("whitelist-entry", mint, wallet),
+    whitelistStats: hookPda("whitelist-stats", mint),
+    freezeAuthority: hookPda("freeze-authority", mint),
+    tokenProgram: TOKEN_2022_PROGRAM_ID,
+  });
+  const writable = (tokenAccounts: PublicKey[]) =>
+    tokenAccounts.map((pubkey) => ({
+      pubkey,
+      isSigner: false,
+      isWritable: true,
+    }));
+
+  const addToWhitelist = (
+    mint: PublicKey,
+    wallet: PublicKey,
+    tokenAccounts: PublicKey[] = []
+  ) =>
+    hook.methods
+      .addToWhitelist()
+      .accountsPartial({
+        ...whitelistAccounts(mint, wallet),
+        newAccount: wallet,
+        systemProgram: SystemProgram.programId,
+      })
+      .remainingAccounts(writable(tokenAccounts))
+      .rpc();
+
+  // Pools register their PDA as exempt through the hook, which only accepts
+  // programs trusted by its upgrade authority
+  const trustAmm = async () => {
+    const trustedProgram = hookPda("trusted-program", amm.programId);
+    if ((await connection.getAccountInfo(trustedProgram)) !== null) {
+      return;
+    }
+    await hook.methods
+      .registerTrustedProgram(amm.programId)
+      .accountsPartial({
+        authority: payer.publicKey,
+        program: hook.programId,
+        programData: findPda(BPF_LOADER_UPGRADEABLE_ID, [hook.programId]),
+        trustedProgram,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc();
+  };
+
+  // Pool of two Token-2022 mints, with an LP token account for the provider
+  // wallet
+  const createPool = async (
+    tokenAMint: PublicKey,
+    tokenBMint: PublicKey,
+    allowHighRisk = false
+  ) => {
+    await trustAmm();
+    const pool = ammPda("pool", tokenAMint, tokenBMint);
+    const accounts = {
+      pool,
+      tokenAMint,
+      tokenBMint,
+      tokenAVault: ammPda("token_a_vault", pool),
+      tokenBVault: ammPda("token_b_vault", pool),
+      lpTokenMint: ammPda("lp_token_mint", pool),
+      tokenAProgram: TOKEN_2022_PROGRAM_ID,
+      tokenBProgram: TOKEN_2022_PROGRAM_ID,
+      lpTokenProgram: TOKEN_PROGRAM_ID,
+      systemProgram: SystemProgram.programId,
+    };
+    await amm.methods
+      .initializePool(new BN(30), { allowHighRisk, trustedHookPrograms: [] })
+      .accountsPartial({
+        ...accounts,
+        authority: payer.publicKey,
+        hookTrustedProgram: hookPda("trusted-program", amm.programId),
+        poolExemption: hookPda("exempt-authority", pool),
+        transferHookProgram: hook.programId,
+      })
+      .rpc();
+    await createAta(accounts.lpTokenMint, payer.publicKey, TOKEN_PROGRAM_ID);
+    return accounts;
+  };
+  type Pool = Awaited<ReturnType<typeof createPool>>;
+
+  // Accounts of the provider wallet trading against `pool`
+  const userAccounts = (pool: Pool) => ({
+    ...pool,
+    user: payer.publicKey,
+    userTokenA: ata(pool.tokenAMint, payer.publicKey),
+    userTokenB: ata(pool.tokenBMint, payer.publicKey),
+    userLpToken: ata(pool.lpTokenMint, payer.publicKey, TOKEN_PROGRAM_ID),
+  });
+
+  const addLiquidity = (pool: Pool, amountA: number, amountB: number) =>
+    amm.methods
+      .addLiquidity(new BN(amountA), new BN(amountB), new BN(0), new BN(0))
+      .accountsPartial(userAccounts(pool))
+      .remainingAccounts(
+        combine(
+          hookAccounts(pool.tokenAMint, payer.publicKey, pool.pool),
+          hookAccounts(pool.tokenBMint, payer.publicKey, pool.pool)
+        )
+      )
+      .preInstructions([computeBudget])
+      .rpc();
+
+  const swap = (pool: Pool, amountIn: number, aToB: boolean) => {
+    const [mintIn, mintOut] = aToB
+      ? [pool.tokenAMint, pool.tokenBMint]
+      : [pool.tokenBMint, pool.tokenAMint];
+    return amm.methods
+      .swap(new BN(amountIn), new BN(0), aToB)
+      .accountsPartial(userAccounts(pool))
+      .remainingAccounts(
+        combine(
+          hookAccounts(mintIn, payer.publicKey, pool.pool),
+          hookAccounts(mintOut, pool.pool, payer.publicKey)
+        )
+      )
+      .preInstructions([computeBudget])
+      .rpc();
+  };
+
+  describe("whitelist", () => {
+    const member = Keypair.generate().publicKey;
+    let mint: PublicKey;
+    let memberAccount: PublicKey;
+
+    before(async () => {
+      mint = await createHookedMint(1_000_000_000);
+      memberAccount = await createAta(mint, member);
+      await initializeWhitelist(mint);
+    });
+
+    it("rejects transfers to wallets outside the mint's whitelist", async () => {
+      await expectFailure(
+        send([transferInstruction(mint, payer.publicKey, member, 1_000)]),
+        "DestinationNotWhitelisted"
+      );
+    });
+
+    it("admits transfers once the wallet is added", async () => {
+      await addToWhitelist(mint, member, [memberAccount]);
+      await send([transferInstruction(mint, payer.publicKey, member, 1_000)]);
+
+      expect(await balance(memberAccount)).to.equal(1_000);
+      const stats = await hook.account.whitelistStats.fetch(
+        hookPda("whitelist-stats", mint)
+      );
+      expect(stats.memberCount).to.equal(1);
+    });
+
+    it("freezes the wallet's token accounts when it is removed", async () => {
+      await hook.methods
+        .removeFromWhitelist()
+        .accountsPartial({
+          ...whitelistAccounts(mint, member),
+          accountToRemove: member,
+          exemption: hookPda("exempt-authority", member),
+        })
+        .remainingAccounts(writable([memberAccount]))
+        .rpc();
+
+      expect(await isFrozen(memberAccount)).to.equal(true);
+      expect(
+        await connection.getAccountInfo(hookPda("whitelist-entry", mint, member))
+      ).to.equal(null);
+      const stats = await hook.account.whitelistStats.fetch(
+        hookPda("whitelist-stats", mint)
+      );
+      expect(stats.memberCount).to.equal(0);
+    });
+
+    it("thaws the wallet's token accounts when it is added back", async () => {
+      await addToWhitelist(mint, member, [memberAccount]);
+
+      expect(await isFrozen(memberAccount)).to.equal(false);
+    });
+
+    it("keeps each mint's whitelist separate", async () => {
+      const otherMint = await createHookedMint(1_000_000_000);
+      await createAta(otherMint, member);
+      await initializeWhitelist(otherMint);
+
+      await expectFailure(
+        send([transferInstruction(otherMint, payer.publicKey, member, 1_000)]),
+        "DestinationNotWhitelisted"
+      );
+    });
+  });
+
+  describe("sell tax", () => {
+    const TAX_BPS = 500;
+    const collector = Keypair.generate();
+    let taxedMint: PublicKey;
+    let pool: Pool;
+    let collectorAccount: PublicKey;
+
+    const taxLedger = () => hookPda("tax-ledger", taxedMint, payer.publicKey);
+    const owed = async () =>
+      (await hook.account.taxLedger.fetch(taxLedger())).owed.toNumber();
+
+    // Settlement by `signer` through the AMM, which signs as the mint's
+    // permanent delegate
+    const settle = (signer: Keypair, amount: number) => {
+      const permanentDelegate = ammPda("permanent-delegate", taxedMint);
+      return amm.methods
+        .settleSellTax(new BN(amount))
+        .accountsPartial({
+          collector: signer.publicKey,
+          mint: taxedMint,
+          sellTaxConfig: hookPda("sell-tax", taxedMint),
+          sellerToken: ata(taxedMint, payer.publicKey),
+          collectorToken: ata(taxedMint, signer.publicKey),
+          permanentDelegate,
+          transferHookProgram: hook.programId,
+          tokenProgram: TOKEN_2022_PROGRAM_ID,
+        })
+        .remainingAccounts(
+          hookAccounts(
+            taxedMint,
+            payer.publicKey,
+            signer.publicKey,
+            permanentDelegate
+          )
+        )
+        .preInstructions([computeBudget])
+        .signers([signer])
+        .rpc();
+    };
+
+    before(async () => {
+      // The recovery PDA is the permanent delegate settlement goes through,
+      // which makes pools of the mint high-risk
+      taxedMint = await createHookedMint(1_000_000_000, { recovery: true });
+      const otherMint = await createHookedMint(1_000_000_000);
+      collectorAccount = await createAta(taxedMint, collector.publicKey);
+
+      await hook.methods
+        .initializeSellTax(TAX_BPS, collector.publicKey)
+        .accountsPartial({
+          authority: payer.publicKey,
+          mint: taxedMint,
+          ruleAuthority: hookPda("rule-authority", taxedMint),
+          sellTaxConfig: hookPda("sell-tax", taxedMint),
+          confidentialPolicy: hookPda("confidential-policy", taxedMint),
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+      pool = await createPool(taxedMint, otherMint, true);
+    });
+
+    it("opens the depositor's tax ledger and taxes liquidity deposits", async () => {
+      expect(await connection.getAccountInfo(taxLedger())).to.equal(null);
+
+      await addLiquidity(pool, 100_000, 100_000);
+
+      expect(await owed()).to.equal((100_000 * TAX_BPS) / 10_000);
+      // The other mint is untaxed, so no ledger is opened for it
+      expect(
+        await connection.getAccountInfo(
+          hookPda("tax-ledger", pool.tokenBMint, payer.publicKey)
+        )
+      ).to.equal(null);
+    });
+
+    it("taxes swaps into the pool but not out of it", async () => {
+      await swap(pool, 10_000, true);
+      expect(await owed()).to.equal(5_000 + 500);
+
+      await swap(pool, 10_000, false);
+      expect(await owed()).to.equal(5_500);
+    });
+
+    it("refuses settlement signed by anyone but the collector", async () => {
+      const impostor = Keypair.generate();
+      await createAta(taxedMint, impostor.publicKey);
+
+      await expectFailure(settle(impostor, 5_500), "NotTaxCollector");
+    });
+
+    it("lets the collector settle owed tax", async () => {
+      await settle(collector, 5_500);
+
+      expect(await balance(collectorAccount)).to.equal(5_500);
+      expect(await owed()).to.equal(0);
+    });
+
+    it("refuses settling more than is owed", async () => {
+      await expectFailure(settle(collector, 1), "SettlementExceedsOwedTax");
+    });
+
+    it("refuses direct sells from wallets without a tax ledger", async () => {
+      const seller = Keypair.generate();
+      await createAta(taxedMint, seller.publicKey);
+      await send([
+        transferInstruction(taxedMint, payer.publicKey, seller.publicKey, 10_000),
+      ]);
+
+      await expectFailure(
+        send(
+          [
+            transferInstruction(
+              taxedMint,
+              seller.publicKey,
+              pool.pool,
+              1_000,
+              pool.tokenAVault
+            ),
+          ],
+          [seller]
+        ),
+        "TaxLedgerMissing"
+      );
+    });
+  });
+
+  describe("recovery", () => {
+    const holder = Keypair.generate();
+    const rescue = Keypair.generate();
+
+    const recoveryConfig = async (mint: PublicKey) =>
+      hook.account.recoveryConfig.fetch(hookPda("recovery", mint));
+
+    const initializeRecovery = (mint: PublicKey) =>
+      hook.methods
+        .initializeRecovery()
+        .accountsPartial({
+          authority: payer.publicKey,
+          mint,
+          ruleAuthority: hookPda("rule-authority", mint),
+          recoveryConfig: hookPda("recovery", mint),
+          extraAccountMetaList: hookPda("extra-account-metas", mint),
+          sanctionsConfig: hookPda("sanctions-config", mint),
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+
+    const authorizeForcedTransfer = (
+      mint: PublicKey,
+      source: PublicKey,
+      destination: PublicKey,
+      amount: number,
+      recoveryConfig = hookPda("recovery", mint)
+    ) =>
+      hook.methods
+        .authorizeForcedTransfer(source, destination, new BN(amount))
+        .accountsPartial({ authority: payer.publicKey, mint, recoveryConfig })
+        .rpc();
+
+    // Mint with `holder` and `rescue` token accounts, `holder` funded
+    const createRecoverableMint = async (options: {
+      recovery?: boolean;
+      permanentDelegate?: PublicKey;
+    }) => {
+      const mint = await createHookedMint(1_000_000_000, options);
+      await createAta(mint, holder.publicKey);
+      await createAta(mint, rescue.publicKey);
+      await send([transferInstruction(mint, payer.publicKey, holder.publicKey, 10_000)]);
+      return mint;
+    };
+
+    describe("through the permanent delegate", () => {
+      const delegate = Keypair.generate();
+      let mint: PublicKey;
+
+      // Transfer signed by the delegate
+      const forced = (
+        sourceOwner: PublicKey,
+        destinationOwner: PublicKey,
+        amount: number
+      ) =>
+        send(
+          [
+            transferInstruction(
+              mint,
+              sourceOwner,
+              destinationOwner,
+              amount,
+              ata(mint, destinationOwner),
+              delegate.publicKey
+            ),
+          ],
+          [delegate]
+        );
+
+      before(async () => {
+        mint = await createRecoverableMint({
+          permanentDelegate: delegate.publicKey,
+        });
+        // `holder` is funded before the whitelist exists, after which only
+        // the staged transfer may reach a wallet that is not whitelisted
+        await initializeWhitelist(mint);
+        await initializeRecovery(mint);
+        await authorizeForcedTransfer(
+          mint,
+          ata(mint, holder.publicKey),
+          ata(mint, rescue.publicKey),
+          1_000
+        );
+      });
+
+      it("does not bypass rules for transfers differing from the staged one", async () => {
+        const other = Keypair.generate();
+        await createAta(mint, other.publicKey);
+
+        // Different amount, destination and source
+        await expectFailure(
+          forced(holder.publicKey, rescue.publicKey, 999),
+          "DestinationNotWhitelisted"
+        );
+        await expectFailure(
+          forced(holder.publicKey, other.publicKey, 1_000),
+          "DestinationNotWhitelisted"
+        );
+        await expectFailure(
+          forced(payer.publicKey, rescue.publicKey, 1_000),
+          "DestinationNotWhitelisted"
+        );
+        expect((await recoveryConfig(mint)).pending).to.not.equal(null);
+      });
+
+      it("executes the staged transfer and clears it", async () => {
+        await forced(holder.publicKey, rescue.publicKey, 1_000);
+
+        expect(await balance(ata(mint, rescue.publicKey))).to.equal(1_000);
+        expect((await recoveryConfig(mint)).pending).to.equal(null);
+      });
+
+      it("executes a staged transfer only once", async () => {
+        await expectFailure(
+          forced(holder.publicKey, rescue.publicKey, 1_000),
+          "DestinationNotWhitelisted"
+        );
+      });
+    });
+
+    describe("through the AMM", () => {
+      let mint: PublicKey;
+      let otherMint: PublicKey;
+
+      const forceTransfer = (
+        amount: number,
+        recoveryConfig = hookPda("recovery", mint)
+      ) => {
+        const permanentDelegate = ammPda("permanent-delegate", mint);
+        return amm.methods
+          .forceTransfer(new BN(amount))
+          .accountsPartial({
+            recoveryAuthority: payer.publicKey,
+            mint,
+            sourceToken: ata(mint, holder.publicKey),
+            destinationToken: ata(mint, rescue.publicKey),
+            permanentDelegate,
+            recoveryConfig,
+            transferHookProgram: hook.programId,
+            tokenProgram: TOKEN_2022_PROGRAM_ID,
+          })
+          .remainingAccounts(
+            hookAccounts(mint, holder.publicKey, rescue.publicKey, permanentDelegate)
+          )
+          .preInstructions([computeBudget])
+          .rpc();
+      };
+
+      before(async () => {
+        mint = await createRecoverableMint({ recovery: true });
+        otherMint = await createHookedMint(1_000_000_000, { recovery: true });
+        await initializeRecovery(mint);
+        await initializeRecovery(otherMint);
+      });
+
+      it("stages and executes a forced transfer", async () => {
+        await forceTransfer(1_000);
+
+        expect(await balance(ata(mint, rescue.publicKey))).to.equal(1_000);
+        expect((await recoveryConfig(mint)).pending).to.equal(null);
+      });
+
+      it("rejects another mint's recovery config", async () => {
+        await expectFailure(
+          forceTransfer(1_000, hookPda("recovery", otherMint)),
+          "ConstraintSeeds"
+        );
+        await expectFailure(
+          authorizeForcedTransfer(
+            mint,
+            ata(mint, holder.publicKey),
+            ata(mint, rescue.publicKey),
+            1_000,
+            hookPda("recovery", otherMint)
+          ),
+          "ConstraintSeeds"
+        );
+        expect((await recoveryConfig(otherMint)).pending).to.equal(null);
+      });
+    });
+  });
+
+  describe("pool of hooked mints", () => {
+    let pool: Pool;
+
+    // Reserves recorded by the pool, and the provider wallet's balances
+    const reserves = async () => {
+      const state = await amm.account.pool.fetch(pool.pool);
+      return [state.tokenAReserve.toNumber(), state.tokenBReserve.toNumber()];
+    };
+    const userBalances = async () => [
+      await balance(ata(pool.tokenAMint, payer.publicKey)),
+      await balance(ata(pool.tokenBMint, payer.publicKey)),
+    ];
+
+    // Check that the vaults hold the recorded reserves and that the
+    // provider wallet's balances moved opposite to them
+    const expectSettled = async (before: number[][], after: number[][]) => {
+      const [[reserveA, reserveB], [userA, userB]] = after;
+      expect(await balance(pool.tokenAVault)).to.equal(reserveA);
+      expect(await balance(pool.tokenBVault)).to.equal(reserveB);
+      expect(userA - before[1][0]).to.equal(before[0][0] - reserveA);
+      expect(userB - before[1][1]).to.equal(before[0][1] - reserveB);
+    };
+    const snapshot = async () => [await reserves(), await userBalances()];
+
+    before(async () => {
+      // Only token A is whitelisted; the pool PDA is exempt through the
+      // exemption registered when the pool is created
+      const tokenAMint = await createHookedMint(1_000_000_000);
+      const tokenBMint = await createHookedMint(1_000_000_000);
+      await initializeWhitelist(tokenAMint);
+      await addToWhitelist(tokenAMint, payer.publicKey);
+      pool = await createPool(tokenAMint, tokenBMint);
+    });
+
+    it("takes deposits of both mints", async () => {
+      const before = await snapshot();
+      await addLiquidity(pool, 100_000, 200_000);
+      const after = await snapshot();
+
+      expect(after[0]).to.deep.equal([100_000, 200_000]);
+      await expectSettled(before, after);
+      expect(
+        await balance(ata(pool.lpTokenMint, payer.publicKey, TOKEN_PROGRAM_ID))
+      ).to.be.greaterThan(0);
+    });
+
+    it("swaps in both directions", async () => {
+      for (const aToB of [true, false]) {
+        const before = await snapshot();
+        await swap(pool, 10_000, aToB);
+        const after = await snapshot();
+
+        await expectSettled(before, after);
+        const [inIndex, outIndex] = aToB ? [0, 1] : [1, 0];
+        expect(after[0][inIndex] - before[0][inIndex]).to.equal(10_000);
+        expect(after[0][outIndex]).to.be.lessThan(before[0][outIndex]);
+      }
+    });
+
+    it("returns both mints on withdrawal", async () => {
+      const userLpToken = ata(pool.lpTokenMint, payer.publicKey, TOKEN_PROGRAM_ID);
+      const lpTokens = await balance(userLpToken);
+      const withdrawn = Math.floor(lpTokens / 2);
+      const before = await snapshot();
 
-  const program = anchor.workspace.token2022AmmHackathon as Program<Token2022AmmHackathon>;
+      await amm.methods
+        .removeLiquidity(new BN(withdrawn), new BN(0), new BN(0))
+        .accountsPartial(userAccounts(pool))
+        .remainingAccounts(
+          combine(
+            hookAccounts(pool.tokenAMint, pool.pool, payer.publicKey),
+            hookAccounts(pool.tokenBMint, pool.pool, payer.publicKey)
+          )
+        )
+        .preInstructions([computeBudget])
+        .rpc();
+      const after = await snapshot();
 
-  it("Is initialized!", async () => {
-    // Add your test here.
-    const tx = await program.methods.initialize().rpc();
-    console.log("Your transaction signature", tx);
+      await expectSettled(before, after);
+      expect(after[0][0]).to.be.lessThan(before[0][0]);
+      expect(after[0][1]).to.be.lessThan(before[0][1]);
+      expect(await balance(userLpToken)).to.equal(lpTokens - withdrawn);
+    });
   });
 });